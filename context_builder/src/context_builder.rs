@@ -6,13 +6,18 @@ use rocket::{Outcome, State};
 use rocket::request::{self, Request, FromRequest, FlashMessage};
 use serde::Serialize;
 
-use meta::Metadata;
+use meta::{Metadata, SidebarItem};
 use nav::{MenuBuilder, Link, EMPTY_MENU};
+use sidebar::SidebarProviders;
+use csrf::{CsrfToken, CSRF_FIELD};
 
 #[derive(Debug, Serialize)]
 pub struct TemplateContext<'s, T: Serialize> {
     meta: &'s Metadata,
     menus: HashMap<String, Vec<Link>>,
+    sidebar: HashMap<String, SidebarItem>,
+    breadcrumbs: Vec<Link>,
+    hidden_fields: HashMap<String, String>,
     data: T,
     alerts: Vec<Alert>,
 }
@@ -23,6 +28,9 @@ pub struct TemplateContext<'s, T: Serialize> {
 pub struct ContextBuilder<'s, T: Serialize> {
     meta: &'s Metadata,
     menu_builders: HashMap<String, MenuBuilder>,
+    sidebar: HashMap<String, SidebarItem>,
+    breadcrumbs: Vec<Link>,
+    hidden_fields: HashMap<String, String>,
     data: PhantomData<*const T>,
     alerts: Vec<Alert>,
 }
@@ -36,6 +44,10 @@ impl<'a, 'r, T: Serialize> FromRequest<'a, 'r> for ContextBuilder<'a, T> {
 
         let mut cb = ContextBuilder::new(meta);
 
+        if let Some(token) = request.guard::<CsrfToken>().succeeded() {
+            cb.add_hidden_field(CSRF_FIELD, token.value());
+        }
+
         if let Some(flash) = flash {
             cb.add_alert(flash.into());
         }
@@ -46,12 +58,23 @@ impl<'a, 'r, T: Serialize> FromRequest<'a, 'r> for ContextBuilder<'a, T> {
 
 impl<'s, T: Serialize> ContextBuilder<'s, T> {
     /// Creates a new `TemplateBuilder` from the given [`Metadata`].
+    /// Dynamic sidebar items are left out until [`load_sidebar`] is called.
     ///
     /// [`Metadata`]: ../metadata/struct.Metadata.html
+    /// [`load_sidebar`]: #method.load_sidebar
     pub fn new(meta: &'s Metadata) -> Self {
+        let sidebar = meta.sidebar()
+            .iter()
+            .filter(|&(_, item)| !item.is_dynamic())
+            .map(|(title, item)| (title.clone(), item.clone()))
+            .collect();
+
         ContextBuilder {
             meta,
             menu_builders: HashMap::new(),
+            sidebar,
+            breadcrumbs: Vec::new(),
+            hidden_fields: HashMap::new(),
             data: PhantomData,
             alerts: Vec::new(),
         }
@@ -90,6 +113,46 @@ impl<'s, T: Serialize> ContextBuilder<'s, T> {
         )
     }
 
+    /// Loads the contents of all dynamic sidebar items, passing `context` to their providers.
+    /// Items whose contents can't be loaded are left out.
+    pub fn load_sidebar<C>(&mut self, providers: &SidebarProviders<C>, context: &C) {
+        for (title, item) in self.meta.sidebar() {
+            if let SidebarItem::Dynamic { ref provider, ref options } = *item {
+                if let Some(item) = providers.provide(provider, context, options) {
+                    self.sidebar.insert(title.clone(), item);
+                }
+            }
+        }
+    }
+
+    /// Appends a link to the breadcrumb trail.
+    /// The links should be added from the outermost to the current page.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use context_builder::ContextBuilder;
+    /// use context_builder::Metadata;
+    /// use context_builder::nav::Link;
+    ///
+    /// let metadata = Metadata::new();
+    /// let mut context_builder: ContextBuilder<()> = ContextBuilder::new(&metadata);
+    ///
+    /// context_builder.add_breadcrumb(Link::new("Docs", "/docs"));
+    /// context_builder.add_breadcrumb(Link::new("Install", "/docs/install"));
+    ///
+    /// let context = context_builder.finalize_with_default();
+    /// ```
+    pub fn add_breadcrumb(&mut self, link: Link) {
+        self.breadcrumbs.push(link);
+    }
+
+    /// Adds a hidden field that should be included in every form of the page.
+    /// An existing field with the same name is replaced.
+    pub fn add_hidden_field<N: ToString, V: ToString>(&mut self, name: N, value: V) {
+        self.hidden_fields.insert(name.to_string(), value.to_string());
+    }
+
     /// Adds an alert to the internal list.
     pub fn add_alert(&mut self, alert: Alert) {
         self.alerts.push(alert);
@@ -107,6 +170,9 @@ impl<'s, T: Serialize> ContextBuilder<'s, T> {
         TemplateContext {
             meta: self.meta,
             menus,
+            sidebar: self.sidebar,
+            breadcrumbs: self.breadcrumbs,
+            hidden_fields: self.hidden_fields,
             data,
             alerts: self.alerts,
         }
@@ -0,0 +1,145 @@
+use std::io::Read;
+
+use ring::constant_time;
+use ring::rand::{SecureRandom, SystemRandom};
+use rocket::{Data, Outcome, Request};
+use rocket::data::{self, FromData};
+use rocket::http::{Cookie, Status};
+use rocket::request::{self, FromRequest, FromForm, FormItems};
+
+/// Name of the hidden form field containing the token.
+/// [`ContextBuilder`] adds it to the hidden fields of every context.
+///
+/// [`ContextBuilder`]: ../context_builder/struct.ContextBuilder.html
+pub const CSRF_FIELD: &'static str = "csrf_token";
+
+/// Name of the private cookie holding the token of the current visitor.
+const CSRF_COOKIE: &'static str = "csrf_token";
+
+/// Maximum size of a form submission in bytes.
+const LIMIT: u64 = 1024 * 1024;
+
+/// Request guard for the CSRF token of the current visitor.
+/// The token is created and stored in a private cookie on first use.
+#[derive(Debug, Clone)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+
+    /// Compares the submitted token with this one in constant time.
+    pub fn verify(&self, submitted: &str) -> bool {
+        constant_time::verify_slices_are_equal(self.0.as_bytes(), submitted.as_bytes()).is_ok()
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for CsrfToken {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<CsrfToken, ()> {
+        let mut cookies = request.cookies();
+
+        if let Some(cookie) = cookies.get_private(CSRF_COOKIE) {
+            return Outcome::Success(CsrfToken(cookie.value().to_string()));
+        }
+
+        let token = match random_token() {
+            Some(token) => token,
+            None => return Outcome::Failure((Status::InternalServerError, ())),
+        };
+        cookies.add_private(
+            Cookie::build(CSRF_COOKIE, token.clone())
+                .path("/")
+                .http_only(true)
+                .finish(),
+        );
+
+        Outcome::Success(CsrfToken(token))
+    }
+}
+
+fn random_token() -> Option<String> {
+    let mut bytes = [0; 32];
+    SystemRandom::new().fill(&mut bytes).ok()?;
+    Some(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Returns `true` if the form data contains the CSRF token of the visitor that sent the request.
+/// Used by all data guards that parse forms.
+pub fn verify_form(request: &Request, form: &str) -> bool {
+    let token = match request.guard::<CsrfToken>() {
+        Outcome::Success(token) => token,
+        _ => return false,
+    };
+
+    FormItems::from(form)
+        .find(|&(key, _)| key.as_str() == CSRF_FIELD)
+        .and_then(|(_, value)| value.url_decode().ok())
+        .map_or(false, |submitted| token.verify(&submitted))
+}
+
+/// Data guard that works like [`Form`], but fails with `BadRequest` if the form doesn't contain
+/// the CSRF token of the visitor.
+///
+/// ```ignore
+/// #[post("/posts/<id>/edit", data = "<changes>")]
+/// fn edit_post(id: i32, changes: CsrfForm<NewPost>) -> ... {
+///     let changes = changes.into_inner();
+///     // ...
+/// }
+/// ```
+///
+/// Use `CsrfForm<NoFields>` for forms that only consist of a button.
+///
+/// [`Form`]: https://api.rocket.rs/rocket/request/struct.Form.html
+pub struct CsrfForm<T> {
+    form: T,
+}
+
+impl<T> CsrfForm<T> {
+    pub fn into_inner(self) -> T {
+        self.form
+    }
+}
+
+impl<T> FromData for CsrfForm<T>
+where
+    T: for<'f> FromForm<'f>,
+{
+    type Error = Option<String>;
+
+    fn from_data(request: &Request, data: Data) -> data::Outcome<Self, Self::Error> {
+        if !request.content_type().map_or(false, |ct| ct.is_form()) {
+            return Outcome::Failure((Status::BadRequest, Some("not a form".to_string())));
+        }
+
+        let mut body = String::new();
+        if let Err(e) = data.open().take(LIMIT).read_to_string(&mut body) {
+            return Outcome::Failure((Status::InternalServerError, Some(format!("{:?}", e))));
+        }
+
+        if !verify_form(request, &body) {
+            return Outcome::Failure((Status::BadRequest, Some("invalid csrf token".to_string())));
+        }
+
+        let mut items = FormItems::from(body.as_str());
+        match T::from_form(&mut items, false) {
+            Ok(form) => Outcome::Success(CsrfForm { form }),
+            Err(_) => Outcome::Failure((Status::UnprocessableEntity, None)),
+        }
+    }
+}
+
+/// A form without any fields besides the CSRF token, for buttons like "Delete" or "Logout".
+#[derive(Debug, Clone, Copy)]
+pub struct NoFields;
+
+impl<'f> FromForm<'f> for NoFields {
+    type Error = ();
+
+    fn from_form(_items: &mut FormItems<'f>, _strict: bool) -> Result<NoFields, ()> {
+        Ok(NoFields)
+    }
+}
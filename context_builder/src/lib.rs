@@ -7,6 +7,7 @@ extern crate serde;
 extern crate serde_derive;
 extern crate regex;
 extern crate toml;
+extern crate ring;
 #[macro_use]
 extern crate maplit;
 
@@ -16,6 +17,12 @@ pub mod meta;
 /// Contains helpers for building menus and links.
 pub mod nav;
 
+/// Contains providers for dynamically loaded sidebar items.
+pub mod sidebar;
+
+/// Contains the CSRF token guard and the form data guard checking it.
+pub mod csrf;
+
 /// Contains [`ContextBuilder`].
 /// Which is a useful guard for all routes that return a [`Template`].
 ///
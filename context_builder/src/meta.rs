@@ -9,6 +9,7 @@ use toml;
 use serde::de::{self, Deserialize, Deserializer, Visitor};
 
 use nav::Link;
+use sidebar::SidebarOptions;
 
 /// This struct is used to hold meta data for contexts to be passed to [`Template::render`]
 ///
@@ -17,6 +18,10 @@ use nav::Link;
 pub struct Metadata {
     title: String,
     description: String,
+    /// The public base url of the site, e.g. `https://blog.example.com`.
+    /// Used wherever absolute urls are needed.
+    #[serde(default)]
+    url: String,
     authors: Vec<Author>,
     #[serde(default)]
     sidebar: HashMap<String, SidebarItem>,
@@ -51,6 +56,7 @@ impl Metadata {
         Metadata {
             title: String::new(),
             description: String::new(),
+            url: String::new(),
             authors: Vec::new(),
             sidebar: HashMap::new(),
             menus: HashMap::new(),
@@ -67,6 +73,11 @@ impl Metadata {
         self.description.as_ref()
     }
 
+    /// Getter for `Metadata.url`.
+    pub fn url(&self) -> &str {
+        self.url.as_ref()
+    }
+
     /// Getter for `Metadata.authors`.
     pub fn authors(&self) -> &[Author] {
         self.authors.as_ref()
@@ -157,7 +168,29 @@ pub enum SidebarItem {
     Text(String),
     TextInset(String),
     Links(Vec<Link>),
-    // TODO add dynamically loaded sidebar item
+    /// A search box for the posts and pages.
+    Search,
+    /// Loaded by the [`SidebarProvider`] registered under the name `provider` once the route calls
+    /// `ContextBuilder::load_sidebar`, left out otherwise.
+    ///
+    /// [`SidebarProvider`]: ../sidebar/trait.SidebarProvider.html
+    Dynamic {
+        provider: String,
+        #[serde(default)]
+        options: SidebarOptions,
+    },
+}
+
+impl SidebarItem {
+    /// Whether the contents of the item have to be loaded by a [`SidebarProvider`].
+    ///
+    /// [`SidebarProvider`]: ../sidebar/trait.SidebarProvider.html
+    pub fn is_dynamic(&self) -> bool {
+        match *self {
+            SidebarItem::Dynamic { .. } => true,
+            _ => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -192,6 +225,7 @@ mod tests {
             Metadata {
                 title: "Some Title".to_string(),
                 description: "Some description".to_string(),
+                url: String::new(),
                 authors: vec![
                     Author {
                         name: "Name".to_string(),
@@ -275,6 +309,45 @@ mod tests {
             ])
         );
 
+        let data = r#"
+            type = "search"
+        "#;
+        let sidebar_item: SidebarItem = toml::from_str(data).unwrap();
+
+        assert_eq!(sidebar_item, SidebarItem::Search);
+
+        let data = r#"
+            type = "dynamic"
+            content = { provider = "recent_posts", options = { count = 3 } }
+        "#;
+        let sidebar_item: SidebarItem = toml::from_str(data).unwrap();
+
+        let mut options = SidebarOptions::new();
+        options.insert("count".to_string(), toml::Value::Integer(3));
+        assert_eq!(
+            sidebar_item,
+            SidebarItem::Dynamic {
+                provider: "recent_posts".to_string(),
+                options,
+            }
+        );
+
+        let data = r#"
+            type = "dynamic"
+            content = { provider = "tag_cloud" }
+        "#;
+        let sidebar_item: SidebarItem = toml::from_str(data).unwrap();
+
+        assert_eq!(
+            sidebar_item,
+            SidebarItem::Dynamic {
+                provider: "tag_cloud".to_string(),
+                options: SidebarOptions::new(),
+            }
+        );
+
+        assert!(sidebar_item.is_dynamic());
+        assert!(!SidebarItem::Search.is_dynamic());
     }
 
 }
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use toml::value::Table;
+
+use meta::SidebarItem;
+
+/// The options of a dynamic sidebar item as given in `Satellite.toml`.
+pub type SidebarOptions = Table;
+
+/// Loads the contents of a dynamic sidebar item.
+///
+/// Providers are registered by name in [`SidebarProviders`] and referenced from
+/// `Satellite.toml`:
+///
+/// ```toml
+/// [sidebar."Recent Posts"]
+/// type = "dynamic"
+/// content = { provider = "recent_posts", options = { count = 5 } }
+/// ```
+///
+/// Every `Fn(&C, &SidebarOptions) -> Option<SidebarItem>` is a provider. `C` is whatever the
+/// route passes to [`ContextBuilder::load_sidebar`], e.g. its database connection.
+///
+/// [`SidebarProviders`]: struct.SidebarProviders.html
+/// [`ContextBuilder::load_sidebar`]: ../struct.ContextBuilder.html#method.load_sidebar
+pub trait SidebarProvider<C>: Send + Sync {
+    /// Returns the item to render in place of the dynamic one, or `None` to leave it out.
+    fn provide(&self, context: &C, options: &SidebarOptions) -> Option<SidebarItem>;
+}
+
+impl<C, F> SidebarProvider<C> for F
+where
+    F: Fn(&C, &SidebarOptions) -> Option<SidebarItem> + Send + Sync,
+{
+    fn provide(&self, context: &C, options: &SidebarOptions) -> Option<SidebarItem> {
+        self(context, options)
+    }
+}
+
+/// Registry of all [`SidebarProvider`]s by name.
+/// Dynamic sidebar items are only loaded by routes that pass it to
+/// [`ContextBuilder::load_sidebar`], everywhere else they are left out.
+///
+/// [`SidebarProvider`]: trait.SidebarProvider.html
+/// [`ContextBuilder::load_sidebar`]: ../struct.ContextBuilder.html#method.load_sidebar
+///
+/// # Examples
+///
+/// ```rust
+/// use context_builder::meta::SidebarItem;
+/// use context_builder::sidebar::{SidebarOptions, SidebarProviders};
+///
+/// fn greeting(name: &String, _options: &SidebarOptions) -> Option<SidebarItem> {
+///     Some(SidebarItem::Text(format!("Hello {}!", name)))
+/// }
+///
+/// let providers = SidebarProviders::new().register("greeting", greeting);
+/// assert!(providers.provide("greeting", &"World".to_string(), &SidebarOptions::new()).is_some());
+/// ```
+pub struct SidebarProviders<C> {
+    providers: HashMap<String, Box<SidebarProvider<C>>>,
+}
+
+impl<C> SidebarProviders<C> {
+    /// Creates an empty registry.
+    pub fn new() -> SidebarProviders<C> {
+        SidebarProviders { providers: HashMap::new() }
+    }
+
+    /// Registers a provider under the given name, replacing any provider with the same name.
+    pub fn register<P: SidebarProvider<C> + 'static>(mut self, name: &str, provider: P) -> SidebarProviders<C> {
+        self.providers.insert(name.to_string(), Box::new(provider));
+        self
+    }
+
+    /// Calls the provider with the given name.
+    /// Returns `None` if there is no such provider.
+    pub fn provide(&self, name: &str, context: &C, options: &SidebarOptions) -> Option<SidebarItem> {
+        self.providers.get(name).and_then(|provider| provider.provide(context, options))
+    }
+}
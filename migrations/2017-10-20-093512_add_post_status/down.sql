@@ -0,0 +1,16 @@
+CREATE TABLE old_posts (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR NOT NULL,
+  slug VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  author VARCHAR NOT NULL,
+  published_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO old_posts (id, title, slug, body, author, published_at)
+  SELECT id, title, slug, body, author,
+         CASE WHEN status = 'draft' THEN NULL ELSE published_at END
+  FROM posts;
+
+DROP TABLE posts;
+ALTER TABLE old_posts RENAME TO posts;
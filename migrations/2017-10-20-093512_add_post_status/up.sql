@@ -0,0 +1,17 @@
+CREATE TABLE new_posts (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR NOT NULL,
+  slug VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  author VARCHAR NOT NULL,
+  published_at TIMESTAMP,
+  status VARCHAR NOT NULL DEFAULT 'draft'
+);
+
+INSERT INTO new_posts (id, title, slug, body, author, published_at, status)
+  SELECT id, title, slug, body, author, published_at,
+         CASE WHEN published_at IS NULL THEN 'draft' ELSE 'published' END
+  FROM posts;
+
+DROP TABLE posts;
+ALTER TABLE new_posts RENAME TO posts;
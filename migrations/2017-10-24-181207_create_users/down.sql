@@ -0,0 +1,17 @@
+CREATE TABLE old_posts (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR NOT NULL,
+  slug VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  author VARCHAR NOT NULL,
+  published_at TIMESTAMP,
+  status VARCHAR NOT NULL DEFAULT 'draft'
+);
+
+INSERT INTO old_posts (id, title, slug, body, author, published_at, status)
+  SELECT id, title, slug, body, author, published_at, status FROM posts;
+
+DROP TABLE posts;
+ALTER TABLE old_posts RENAME TO posts;
+
+DROP TABLE users;
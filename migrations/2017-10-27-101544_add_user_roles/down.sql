@@ -0,0 +1,14 @@
+CREATE TABLE old_users (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  username VARCHAR NOT NULL UNIQUE,
+  display_name VARCHAR NOT NULL,
+  email VARCHAR NOT NULL,
+  password_hash VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO old_users (id, username, display_name, email, password_hash, created_at)
+  SELECT id, username, display_name, email, password_hash, created_at FROM users;
+
+DROP TABLE users;
+ALTER TABLE old_users RENAME TO users;
@@ -0,0 +1,4 @@
+ALTER TABLE users ADD COLUMN role VARCHAR NOT NULL DEFAULT 'author';
+
+-- The first user has been managing everything so far.
+UPDATE users SET role = 'admin' WHERE id = (SELECT MIN(id) FROM users);
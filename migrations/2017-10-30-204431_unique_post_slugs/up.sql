@@ -0,0 +1,5 @@
+-- Older posts could end up with the same slug, keep the oldest one as is.
+UPDATE posts SET slug = slug || '-' || id
+  WHERE id NOT IN (SELECT MIN(id) FROM posts GROUP BY slug);
+
+CREATE UNIQUE INDEX posts_slug_unique ON posts (slug);
@@ -0,0 +1,25 @@
+CREATE TABLE tags (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  name VARCHAR NOT NULL,
+  slug VARCHAR NOT NULL UNIQUE
+);
+
+CREATE TABLE categories (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  name VARCHAR NOT NULL,
+  slug VARCHAR NOT NULL UNIQUE
+);
+
+CREATE TABLE post_tags (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  post_id INTEGER NOT NULL REFERENCES posts (id),
+  tag_id INTEGER NOT NULL REFERENCES tags (id),
+  UNIQUE (post_id, tag_id)
+);
+
+CREATE TABLE post_categories (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  post_id INTEGER NOT NULL REFERENCES posts (id),
+  category_id INTEGER NOT NULL REFERENCES categories (id),
+  UNIQUE (post_id, category_id)
+);
@@ -0,0 +1 @@
+ALTER TABLE posts ADD COLUMN views INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+CREATE TABLE pages (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR NOT NULL,
+  slug VARCHAR NOT NULL UNIQUE,
+  body TEXT NOT NULL,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- the pages linked in the default main menu
+INSERT INTO pages (title, slug, body) VALUES
+  ('New features', 'new-features', 'Nothing here yet.'),
+  ('Press', 'press', 'Nothing here yet.'),
+  ('New hires', 'new-hires', 'Nothing here yet.'),
+  ('About', 'about', 'Nothing here yet.');
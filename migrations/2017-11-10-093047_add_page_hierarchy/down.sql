@@ -0,0 +1,14 @@
+CREATE TABLE old_pages (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR NOT NULL,
+  slug VARCHAR NOT NULL UNIQUE,
+  body TEXT NOT NULL,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- flatten the hierarchy, the path is the only thing that's guaranteed to be unique
+INSERT INTO old_pages (id, title, slug, body, updated_at)
+  SELECT id, title, REPLACE(path, '/', '-'), body, updated_at FROM pages;
+
+DROP TABLE pages;
+ALTER TABLE old_pages RENAME TO pages;
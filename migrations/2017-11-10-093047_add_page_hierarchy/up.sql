@@ -0,0 +1,16 @@
+-- slugs only have to be unique between siblings now, the full path is unique instead
+CREATE TABLE new_pages (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR NOT NULL,
+  slug VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  parent_id INTEGER REFERENCES pages (id),
+  path VARCHAR NOT NULL UNIQUE
+);
+
+INSERT INTO new_pages (id, title, slug, body, updated_at, parent_id, path)
+  SELECT id, title, slug, body, updated_at, NULL, slug FROM pages;
+
+DROP TABLE pages;
+ALTER TABLE new_pages RENAME TO pages;
@@ -0,0 +1,11 @@
+CREATE TABLE comments (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  post_id INTEGER NOT NULL REFERENCES posts (id),
+  author_name VARCHAR NOT NULL,
+  author_email VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  status VARCHAR NOT NULL DEFAULT 'pending',
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX comments_post_id ON comments (post_id);
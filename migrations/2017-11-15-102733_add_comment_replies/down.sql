@@ -0,0 +1,17 @@
+CREATE TABLE old_comments (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  post_id INTEGER NOT NULL REFERENCES posts (id),
+  author_name VARCHAR NOT NULL,
+  author_email VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  status VARCHAR NOT NULL DEFAULT 'pending',
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO old_comments (id, post_id, author_name, author_email, body, status, created_at)
+  SELECT id, post_id, author_name, author_email, body, status, created_at FROM comments;
+
+DROP TABLE comments;
+ALTER TABLE old_comments RENAME TO comments;
+
+CREATE INDEX comments_post_id ON comments (post_id);
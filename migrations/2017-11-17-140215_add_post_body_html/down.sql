@@ -0,0 +1,19 @@
+CREATE TABLE old_posts (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR NOT NULL,
+  slug VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  author VARCHAR NOT NULL,
+  published_at TIMESTAMP,
+  status VARCHAR NOT NULL DEFAULT 'draft',
+  author_id INTEGER REFERENCES users (id),
+  views INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO old_posts (id, title, slug, body, author, published_at, status, author_id, views)
+  SELECT id, title, slug, body, author, published_at, status, author_id, views FROM posts;
+
+DROP TABLE posts;
+ALTER TABLE old_posts RENAME TO posts;
+
+CREATE UNIQUE INDEX posts_slug_unique ON posts (slug);
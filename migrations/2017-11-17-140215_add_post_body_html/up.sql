@@ -0,0 +1,2 @@
+-- Existing posts are rendered when the server starts, see `posts::render_missing`.
+ALTER TABLE posts ADD COLUMN body_html TEXT NOT NULL DEFAULT '';
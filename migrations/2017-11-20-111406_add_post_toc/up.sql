@@ -0,0 +1,2 @@
+-- Existing posts are rendered with `satellite rerender`.
+ALTER TABLE posts ADD COLUMN toc_html TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,3 @@
+-- Existing posts are rendered when the server starts, see `posts::render_missing`.
+ALTER TABLE posts ADD COLUMN excerpt TEXT NOT NULL DEFAULT '';
+ALTER TABLE posts ADD COLUMN excerpt_html TEXT NOT NULL DEFAULT '';
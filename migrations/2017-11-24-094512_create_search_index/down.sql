@@ -0,0 +1,9 @@
+DROP TRIGGER search_posts_insert;
+DROP TRIGGER search_posts_delete;
+DROP TRIGGER search_posts_update;
+DROP TRIGGER search_pages_insert;
+DROP TRIGGER search_pages_delete;
+DROP TRIGGER search_pages_update;
+
+DROP TABLE __search_posts;
+DROP TABLE __search_pages;
@@ -0,0 +1,29 @@
+-- The names start with two underscores, so `infer_schema!` skips these tables (and their shadow
+-- tables), it can't handle virtual tables.
+CREATE VIRTUAL TABLE __search_posts USING fts5(title, body, content='posts', content_rowid='id');
+CREATE VIRTUAL TABLE __search_pages USING fts5(title, body, content='pages', content_rowid='id');
+
+CREATE TRIGGER search_posts_insert AFTER INSERT ON posts BEGIN
+  INSERT INTO __search_posts (rowid, title, body) VALUES (new.id, new.title, new.body);
+END;
+CREATE TRIGGER search_posts_delete AFTER DELETE ON posts BEGIN
+  INSERT INTO __search_posts (__search_posts, rowid, title, body) VALUES ('delete', old.id, old.title, old.body);
+END;
+CREATE TRIGGER search_posts_update AFTER UPDATE OF title, body ON posts BEGIN
+  INSERT INTO __search_posts (__search_posts, rowid, title, body) VALUES ('delete', old.id, old.title, old.body);
+  INSERT INTO __search_posts (rowid, title, body) VALUES (new.id, new.title, new.body);
+END;
+
+CREATE TRIGGER search_pages_insert AFTER INSERT ON pages BEGIN
+  INSERT INTO __search_pages (rowid, title, body) VALUES (new.id, new.title, new.body);
+END;
+CREATE TRIGGER search_pages_delete AFTER DELETE ON pages BEGIN
+  INSERT INTO __search_pages (__search_pages, rowid, title, body) VALUES ('delete', old.id, old.title, old.body);
+END;
+CREATE TRIGGER search_pages_update AFTER UPDATE OF title, body ON pages BEGIN
+  INSERT INTO __search_pages (__search_pages, rowid, title, body) VALUES ('delete', old.id, old.title, old.body);
+  INSERT INTO __search_pages (rowid, title, body) VALUES (new.id, new.title, new.body);
+END;
+
+INSERT INTO __search_posts (__search_posts) VALUES ('rebuild');
+INSERT INTO __search_pages (__search_pages) VALUES ('rebuild');
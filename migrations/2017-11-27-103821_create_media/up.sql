@@ -0,0 +1,9 @@
+CREATE TABLE media (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  filename VARCHAR NOT NULL UNIQUE,
+  original_name VARCHAR NOT NULL,
+  mime_type VARCHAR NOT NULL,
+  size INTEGER NOT NULL,
+  uploader_id INTEGER REFERENCES users (id),
+  uploaded_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,17 @@
+DROP TABLE media_variants;
+
+CREATE TABLE old_media (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  filename VARCHAR NOT NULL UNIQUE,
+  original_name VARCHAR NOT NULL,
+  mime_type VARCHAR NOT NULL,
+  size INTEGER NOT NULL,
+  uploader_id INTEGER REFERENCES users (id),
+  uploaded_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO old_media (id, filename, original_name, mime_type, size, uploader_id, uploaded_at)
+  SELECT id, filename, original_name, mime_type, size, uploader_id, uploaded_at FROM media;
+
+DROP TABLE media;
+ALTER TABLE old_media RENAME TO media;
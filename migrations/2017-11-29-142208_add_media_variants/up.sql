@@ -0,0 +1,13 @@
+ALTER TABLE media ADD COLUMN width INTEGER;
+ALTER TABLE media ADD COLUMN height INTEGER;
+
+CREATE TABLE media_variants (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  media_id INTEGER NOT NULL REFERENCES media (id),
+  name VARCHAR NOT NULL,
+  filename VARCHAR NOT NULL UNIQUE,
+  width INTEGER NOT NULL,
+  height INTEGER NOT NULL
+);
+
+CREATE INDEX media_variants_media_id ON media_variants (media_id);
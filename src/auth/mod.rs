@@ -0,0 +1,78 @@
+pub mod permissions;
+
+use rocket::Outcome;
+use rocket::http::{Cookie, Status};
+use rocket::request::{self, Request, FromRequest};
+use rocket::response::{self, Responder};
+
+use db::DbConn;
+use db::models::User;
+use controllers::users;
+
+/// Name of the private cookie holding the id of the logged in user.
+const SESSION_COOKIE: &'static str = "user_id";
+
+/// Retrieves the logged in user from the private session cookie.
+/// If nobody is logged in, fails with an `Unauthorized` status, which is caught and
+/// redirected to the login page.
+///
+/// Use `Option<User>` if a route should also be available without logging in.
+impl<'a, 'r> FromRequest<'a, 'r> for User {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<User, ()> {
+        let db = request.guard::<DbConn>()?;
+        let user_id = request.cookies()
+            .get_private(SESSION_COOKIE)
+            .and_then(|cookie| cookie.value().parse::<i32>().ok());
+
+        match user_id.and_then(|id| users::get_with_id(&db, id).ok()) {
+            Some(user) => Outcome::Success(user),
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/// Starts a new session for the given user before sending `response`.
+///
+/// The cookie is set while responding, because routes taking a [`CsrfForm`] can't hold on to
+/// `Cookies` themselves: the form is checked against a cookie after all request guards ran.
+///
+/// [`CsrfForm`]: ../../context_builder/csrf/struct.CsrfForm.html
+pub fn login<R>(user: &User, response: R) -> Session<R> {
+    Session {
+        user_id: Some(user.id),
+        response,
+    }
+}
+
+/// Ends the current session before sending `response`.
+pub fn logout<R>(response: R) -> Session<R> {
+    Session {
+        user_id: None,
+        response,
+    }
+}
+
+/// Responder returned by [`login`] and [`logout`].
+///
+/// [`login`]: fn.login.html
+/// [`logout`]: fn.logout.html
+pub struct Session<R> {
+    user_id: Option<i32>,
+    response: R,
+}
+
+impl<'r, R: Responder<'r>> Responder<'r> for Session<R> {
+    fn respond_to(self, request: &Request) -> response::Result<'r> {
+        {
+            let mut cookies = request.cookies();
+            match self.user_id {
+                Some(id) => cookies.add_private(Cookie::new(SESSION_COOKIE, id.to_string())),
+                None => cookies.remove_private(Cookie::named(SESSION_COOKIE)),
+            }
+        }
+
+        self.response.respond_to(request)
+    }
+}
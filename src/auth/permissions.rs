@@ -0,0 +1,174 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use rocket::Outcome;
+use rocket::http::Status;
+use rocket::request::{self, Request, FromRequest};
+
+use db::models::User;
+
+/// The role of a [`User`], stored as a lowercase string in the `users.role` column.
+///
+/// [`User`]: ../../db/models/struct.User.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Manages users and site settings, can do everything an editor can do.
+    Admin,
+    /// Can edit and publish everyone's posts.
+    Editor,
+    /// Can write posts and edit their own ones.
+    Author,
+}
+
+impl Role {
+    /// Returns the name of this role as stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Role::Admin => "admin",
+            Role::Editor => "editor",
+            Role::Author => "author",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "admin" => Ok(Role::Admin),
+            "editor" => Ok(Role::Editor),
+            "author" => Ok(Role::Author),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A permission that is granted to some roles.
+/// Checks that depend on a specific object (e.g. whether a post belongs to the user) are done
+/// separately, see [`User::can_edit`].
+///
+/// [`User::can_edit`]: ../../db/models/struct.User.html#method.can_edit
+pub trait Permission {
+    fn is_granted(role: Role) -> bool;
+}
+
+/// Write new posts.
+pub struct CreatePost;
+
+/// Edit and delete posts. Authors are additionally restricted to their own posts.
+pub struct EditPost;
+
+/// Publish and unpublish posts.
+pub struct PublishPost;
+
+/// Approve, reject and delete reader comments.
+pub struct ModerateComments;
+
+/// Create, edit and delete static pages.
+pub struct ManagePages;
+
+/// Create users and change their roles.
+pub struct ManageUsers;
+
+/// Change site wide settings.
+pub struct ManageSettings;
+
+/// Upload files to the media library. Deleting them is checked by [`User::can_delete`].
+///
+/// [`User::can_delete`]: ../../db/models/struct.User.html#method.can_delete
+pub struct UploadMedia;
+
+impl Permission for CreatePost {
+    fn is_granted(_role: Role) -> bool {
+        true
+    }
+}
+
+impl Permission for EditPost {
+    fn is_granted(_role: Role) -> bool {
+        true
+    }
+}
+
+impl Permission for PublishPost {
+    fn is_granted(role: Role) -> bool {
+        role == Role::Admin || role == Role::Editor
+    }
+}
+
+impl Permission for ModerateComments {
+    fn is_granted(role: Role) -> bool {
+        role == Role::Admin || role == Role::Editor
+    }
+}
+
+impl Permission for ManagePages {
+    fn is_granted(role: Role) -> bool {
+        role == Role::Admin || role == Role::Editor
+    }
+}
+
+impl Permission for ManageUsers {
+    fn is_granted(role: Role) -> bool {
+        role == Role::Admin
+    }
+}
+
+impl Permission for ManageSettings {
+    fn is_granted(role: Role) -> bool {
+        role == Role::Admin
+    }
+}
+
+impl Permission for UploadMedia {
+    fn is_granted(_role: Role) -> bool {
+        true
+    }
+}
+
+/// Request guard that only succeeds if the logged in user has the permission `P`.
+/// Fails with `Unauthorized` if nobody is logged in and with `Forbidden` if the permission is
+/// not granted.
+///
+/// ```
+/// #[get("/users")]
+/// fn users(user: RequirePermission<ManageUsers>) -> Template {
+///     // ...
+/// }
+/// ```
+pub struct RequirePermission<P: Permission> {
+    user: User,
+    permission: PhantomData<P>,
+}
+
+impl<P: Permission> RequirePermission<P> {
+    /// Returns the user that was granted the permission.
+    pub fn into_user(self) -> User {
+        self.user
+    }
+}
+
+impl<P: Permission> Deref for RequirePermission<P> {
+    type Target = User;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+impl<'a, 'r, P: Permission> FromRequest<'a, 'r> for RequirePermission<P> {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, ()> {
+        let user = request.guard::<User>()?;
+
+        if P::is_granted(user.role()) {
+            Outcome::Success(RequirePermission { user, permission: PhantomData })
+        } else {
+            Outcome::Failure((Status::Forbidden, ()))
+        }
+    }
+}
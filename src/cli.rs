@@ -0,0 +1,64 @@
+use rpassword;
+
+use db::{self, DbConn};
+use auth::permissions::Role;
+use config::Config;
+use controllers::{posts, users};
+use markdown::Renderer;
+use shortcodes;
+
+const USAGE: &'static str = "Usage:
+    satellite
+        Launches the server.
+    satellite adduser <role> <username> <email> <display name>
+        Creates a new user. The role is one of admin, editor or author.
+    satellite rerender
+        Renders the markdown of all posts again. Needed after changing the markdown,
+        sanitizer or highlighting options.";
+
+/// Runs the command given on the command line and returns the exit status of the process.
+/// Returns `None` if no command was given and the server should be launched instead.
+pub fn run(args: &[String]) -> Option<i32> {
+    let result = match args.get(0).map(|s| s.as_str()) {
+        None => return None,
+        Some("adduser") if args.len() >= 5 => add_user(&args[1], &args[2], &args[3], &args[4..].join(" ")),
+        Some("rerender") => rerender(),
+        _ => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        },
+    }
+}
+
+fn connection() -> Result<DbConn, String> {
+    db::init_pool().get()
+        .map(DbConn)
+        .map_err(|err| format!("Could not connect to the database: {:?}", err))
+}
+
+fn add_user(role: &str, username: &str, email: &str, display_name: &str) -> Result<(), String> {
+    let role: Role = role.parse().map_err(|_| format!("'{}' is not a valid role.", role))?;
+    let db = connection()?;
+    let password = rpassword::prompt_password_stdout("Password: ")
+        .map_err(|err| format!("Could not read the password: {:?}", err))?;
+
+    let user = users::create(&db, username, display_name, email, &password, role)?;
+    println!("Created user '{}' with id {}.", user.username, user.id);
+    Ok(())
+}
+
+fn rerender() -> Result<(), String> {
+    let config = Config::load()?;
+    let renderer = Renderer::new(&config, shortcodes::registry()?);
+    let db = connection()?;
+
+    let count = posts::rerender_all(&db, &renderer)
+        .map_err(|err| format!("Could not render posts: {:?}", err))?;
+    println!("Rendered {} posts.", count);
+    Ok(())
+}
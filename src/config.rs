@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+
+use toml;
+
+/// Settings of satellite itself that are not needed by the templates, read from the same
+/// `Satellite.toml` as the [`Metadata`].
+/// Every section is optional.
+///
+/// [`Metadata`]: ../../context_builder/meta/struct.Metadata.html
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    #[serde(default)]
+    pub spam: SpamConfig,
+    #[serde(default)]
+    pub markdown: MarkdownConfig,
+    #[serde(default)]
+    pub sanitizer: SanitizerConfig,
+    #[serde(default)]
+    pub highlighting: HighlightConfig,
+    #[serde(default)]
+    pub uploads: UploadConfig,
+}
+
+impl Config {
+    /// Reads the config from `Satellite.toml`.
+    /// Unlike [`Metadata`], this is loaded before the rocket is started, because other managed
+    /// state depends on it.
+    ///
+    /// [`Metadata`]: ../../context_builder/meta/struct.Metadata.html
+    pub fn load() -> Result<Config, String> {
+        let mut input = String::new();
+        File::open("Satellite.toml")
+            .and_then(|mut f| f.read_to_string(&mut input))
+            .map_err(|e| e.to_string())?;
+
+        toml::from_str(input.as_str()).map_err(|e| e.to_string())
+    }
+}
+
+/// The `[notifications]` section.
+/// Mails are piped to `command` if it is set, otherwise they are written to the `spool`
+/// directory. If neither is set, no notifications are sent.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationConfig {
+    /// Sender address of all notifications.
+    #[serde(default)]
+    pub from: String,
+    /// A sendmail compatible command, e.g. `/usr/sbin/sendmail -t`.
+    pub command: Option<String>,
+    /// Directory the mails are written to, one file per mail.
+    pub spool: Option<String>,
+}
+
+/// The `[spam]` section, see [`spam::filter`].
+///
+/// [`spam::filter`]: ../spam/fn.filter.html
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpamConfig {
+    /// Key used to sign the form tokens. A random one is generated on every start if this is
+    /// missing, which invalidates all forms that are open at that time.
+    pub secret: Option<String>,
+    /// Forms submitted faster than this after they were rendered are rejected.
+    #[serde(default = "SpamConfig::default_min_submit_seconds")]
+    pub min_submit_seconds: i64,
+    /// Forms submitted later than this after they were rendered are rejected.
+    #[serde(default = "SpamConfig::default_max_submit_seconds")]
+    pub max_submit_seconds: i64,
+    /// Submissions of anonymous users may contain at most this many links.
+    #[serde(default = "SpamConfig::default_max_links")]
+    pub max_links: usize,
+    /// Number of submissions allowed per IP address in `rate_limit_seconds`.
+    #[serde(default = "SpamConfig::default_rate_limit")]
+    pub rate_limit: usize,
+    #[serde(default = "SpamConfig::default_rate_limit_seconds")]
+    pub rate_limit_seconds: u64,
+}
+
+impl SpamConfig {
+    fn default_min_submit_seconds() -> i64 {
+        3
+    }
+
+    fn default_max_submit_seconds() -> i64 {
+        24 * 60 * 60
+    }
+
+    fn default_max_links() -> usize {
+        3
+    }
+
+    fn default_rate_limit() -> usize {
+        5
+    }
+
+    fn default_rate_limit_seconds() -> u64 {
+        10 * 60
+    }
+}
+
+impl Default for SpamConfig {
+    fn default() -> SpamConfig {
+        SpamConfig {
+            secret: None,
+            min_submit_seconds: SpamConfig::default_min_submit_seconds(),
+            max_submit_seconds: SpamConfig::default_max_submit_seconds(),
+            max_links: SpamConfig::default_max_links(),
+            rate_limit: SpamConfig::default_rate_limit(),
+            rate_limit_seconds: SpamConfig::default_rate_limit_seconds(),
+        }
+    }
+}
+
+/// The `[markdown]` section.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarkdownConfig {
+    /// Whether to generate a table of contents for long posts.
+    #[serde(default = "MarkdownConfig::default_table_of_contents")]
+    pub table_of_contents: bool,
+    /// Posts with fewer headings than this don't get a table of contents.
+    #[serde(default = "MarkdownConfig::default_toc_min_headings")]
+    pub toc_min_headings: usize,
+    /// Length of generated excerpts for posts without an explicit excerpt or `<!--more-->`.
+    #[serde(default = "MarkdownConfig::default_excerpt_words")]
+    pub excerpt_words: usize,
+}
+
+impl MarkdownConfig {
+    fn default_table_of_contents() -> bool {
+        true
+    }
+
+    fn default_toc_min_headings() -> usize {
+        3
+    }
+
+    fn default_excerpt_words() -> usize {
+        55
+    }
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> MarkdownConfig {
+        MarkdownConfig {
+            table_of_contents: MarkdownConfig::default_table_of_contents(),
+            toc_min_headings: MarkdownConfig::default_toc_min_headings(),
+            excerpt_words: MarkdownConfig::default_excerpt_words(),
+        }
+    }
+}
+
+/// The `[sanitizer]` section, the allowlist for the html rendered from markdown.
+/// Everything that is not listed here is removed. Syntax highlighting needs the `class`
+/// attribute on `span` elements.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SanitizerConfig {
+    #[serde(default = "SanitizerConfig::default_tags")]
+    pub tags: Vec<String>,
+    /// Allowed attributes per tag.
+    #[serde(default = "SanitizerConfig::default_attributes")]
+    pub attributes: HashMap<String, Vec<String>>,
+    /// Attributes allowed on all tags.
+    #[serde(default = "SanitizerConfig::default_generic_attributes")]
+    pub generic_attributes: Vec<String>,
+    /// Allowed schemes of absolute urls in `href` and `src` attributes. Relative urls are always
+    /// allowed.
+    #[serde(default = "SanitizerConfig::default_url_schemes")]
+    pub url_schemes: Vec<String>,
+}
+
+impl SanitizerConfig {
+    fn default_tags() -> Vec<String> {
+        vec![
+            "a", "abbr", "b", "blockquote", "br", "code", "dd", "del", "div", "dl", "dt", "em",
+            "h1", "h2", "h3", "h4", "h5", "h6", "hr", "i", "img", "input", "ins", "kbd", "li", "ol", "p",
+            "pre", "s", "span", "strong", "sub", "sup", "table", "tbody", "td", "th", "thead", "tr",
+            "ul",
+        ].into_iter().map(|tag| tag.to_string()).collect()
+    }
+
+    fn default_attributes() -> HashMap<String, Vec<String>> {
+        convert_args!(hashmap!(
+            "a" => vec!["href".to_string(), "title".to_string()],
+            "img" => vec![
+                "src".to_string(), "alt".to_string(), "title".to_string(),
+                // set for images from the media library
+                "srcset".to_string(), "sizes".to_string(), "width".to_string(), "height".to_string(),
+            ],
+            // the checkboxes of task lists
+            "input" => vec!["type".to_string(), "checked".to_string(), "disabled".to_string()],
+            "td" => vec!["align".to_string()],
+            "th" => vec!["align".to_string()],
+        ))
+    }
+
+    fn default_generic_attributes() -> Vec<String> {
+        // needed for footnotes, heading anchors and syntax highlighting
+        vec!["class".to_string(), "id".to_string()]
+    }
+
+    fn default_url_schemes() -> Vec<String> {
+        vec!["http".to_string(), "https".to_string(), "mailto".to_string()]
+    }
+}
+
+impl Default for SanitizerConfig {
+    fn default() -> SanitizerConfig {
+        SanitizerConfig {
+            tags: SanitizerConfig::default_tags(),
+            attributes: SanitizerConfig::default_attributes(),
+            generic_attributes: SanitizerConfig::default_generic_attributes(),
+            url_schemes: SanitizerConfig::default_url_schemes(),
+        }
+    }
+}
+
+/// The `[highlighting]` section, syntax highlighting of fenced code blocks with a language tag.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HighlightConfig {
+    #[serde(default = "HighlightConfig::default_enabled")]
+    pub enabled: bool,
+    /// One of the themes bundled with syntect, e.g. `InspiredGitHub` or `base16-ocean.dark`.
+    #[serde(default = "HighlightConfig::default_theme")]
+    pub theme: String,
+    /// File the css of the theme is written to on start.
+    #[serde(default = "HighlightConfig::default_css")]
+    pub css: String,
+}
+
+impl HighlightConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_theme() -> String {
+        "InspiredGitHub".to_string()
+    }
+
+    fn default_css() -> String {
+        "static/highlight.css".to_string()
+    }
+}
+
+impl Default for HighlightConfig {
+    fn default() -> HighlightConfig {
+        HighlightConfig {
+            enabled: HighlightConfig::default_enabled(),
+            theme: HighlightConfig::default_theme(),
+            css: HighlightConfig::default_css(),
+        }
+    }
+}
+
+/// The `[uploads]` section of the media library.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadConfig {
+    /// Directory the uploaded files are stored in, served at `/media`.
+    #[serde(default = "UploadConfig::default_directory")]
+    pub directory: String,
+    /// Maximum size of a single file in bytes.
+    #[serde(default = "UploadConfig::default_max_size")]
+    pub max_size: u64,
+    /// MIME types that may be uploaded, detected from the content of the file.
+    /// Only the types known to [`media::sniff`] can be used.
+    ///
+    /// [`media::sniff`]: ../media/fn.sniff.html
+    #[serde(default = "UploadConfig::default_types")]
+    pub types: Vec<String>,
+    /// Maximum widths of the scaled down copies created for every uploaded image, by name.
+    /// Copies are only created for images that are wider.
+    #[serde(default = "UploadConfig::default_variants")]
+    pub variants: HashMap<String, u32>,
+    /// The `sizes` attribute of images from the media library, tells the browser which width
+    /// the image will be shown at.
+    #[serde(default = "UploadConfig::default_sizes")]
+    pub sizes: String,
+    /// Images with more pixels than this are not decoded, only their size is stored.
+    /// Decoding needs about four bytes per pixel.
+    #[serde(default = "UploadConfig::default_max_pixels")]
+    pub max_pixels: u64,
+}
+
+impl UploadConfig {
+    fn default_directory() -> String {
+        "uploads".to_string()
+    }
+
+    fn default_max_size() -> u64 {
+        5 * 1024 * 1024
+    }
+
+    fn default_types() -> Vec<String> {
+        vec!["image/png", "image/jpeg", "image/gif", "image/webp"]
+            .into_iter().map(|mime| mime.to_string()).collect()
+    }
+
+    fn default_variants() -> HashMap<String, u32> {
+        hashmap!(
+            "thumbnail".to_string() => 150,
+            "medium".to_string() => 640,
+            "large".to_string() => 1280,
+        )
+    }
+
+    fn default_sizes() -> String {
+        "(min-width: 768px) 690px, 100vw".to_string()
+    }
+
+    fn default_max_pixels() -> u64 {
+        40_000_000
+    }
+}
+
+impl Default for UploadConfig {
+    fn default() -> UploadConfig {
+        UploadConfig {
+            directory: UploadConfig::default_directory(),
+            max_size: UploadConfig::default_max_size(),
+            types: UploadConfig::default_types(),
+            variants: UploadConfig::default_variants(),
+            sizes: UploadConfig::default_sizes(),
+            max_pixels: UploadConfig::default_max_pixels(),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use db::models::Category;
+use db::schema::{categories, post_categories};
+
+terms! {
+    model: Category,
+    table: categories,
+    join_table: post_categories,
+    column: category_id,
+    new: NewCategory,
+    new_join: NewPostCategory,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Insertable)]
+#[table_name="categories"]
+struct NewCategory {
+    name: String,
+    slug: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Insertable)]
+#[table_name="post_categories"]
+struct NewPostCategory {
+    post_id: i32,
+    category_id: i32,
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use diesel;
+use diesel::prelude::*;
+
+use db::models::{Comment, CommentStatus};
+use db::schema::comments;
+use db::DbConn;
+use forms::NonEmpty;
+
+/// Replies can't be nested deeper than this. Top level comments have a depth of 0.
+pub const MAX_DEPTH: usize = 3;
+
+/// A comment together with its position in the reply tree.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct ThreadedComment {
+    pub comment: Comment,
+    pub depth: usize,
+    pub can_reply: bool,
+}
+
+pub fn get_with_id(db: &DbConn, comment_id: i32) -> QueryResult<Comment> {
+    use diesel::prelude::*;
+    use db::schema::comments::dsl::*;
+
+    comments.find(comment_id).first(&**db)
+}
+
+/// Loads the approved comments of the given post, oldest first.
+pub fn approved_for_post(db: &DbConn, post: i32) -> QueryResult<Vec<Comment>> {
+    use diesel::prelude::*;
+    use db::schema::comments::dsl::*;
+
+    comments
+        .filter(post_id.eq(post))
+        .filter(status.eq(CommentStatus::Approved.as_str()))
+        .order(created_at.asc())
+        .load::<Comment>(&**db)
+}
+
+/// Loads the approved comments of the given post ordered as a tree: every comment is directly
+/// followed by its replies.
+pub fn threaded_for_post(db: &DbConn, post: i32) -> QueryResult<Vec<ThreadedComment>> {
+    let comments = approved_for_post(db, post)?;
+    let mut threaded = Vec::with_capacity(comments.len());
+    add_replies(&comments, None, 0, &mut threaded);
+    Ok(threaded)
+}
+
+/// Appends the replies to `parent` and all their replies to `threaded`.
+/// Replies to comments that are not in `comments` (e.g. because they are not approved) are
+/// left out.
+fn add_replies(comments: &[Comment], parent: Option<i32>, depth: usize, threaded: &mut Vec<ThreadedComment>) {
+    for comment in comments.iter().filter(|comment| comment.parent_id == parent) {
+        threaded.push(ThreadedComment {
+            comment: comment.clone(),
+            depth,
+            can_reply: depth < MAX_DEPTH,
+        });
+        add_replies(comments, Some(comment.id), depth + 1, threaded);
+    }
+}
+
+/// Returns how deep the given comment is nested.
+fn depth(db: &DbConn, comment: &Comment) -> QueryResult<usize> {
+    let mut depth = 0;
+    let mut parent = comment.parent_id;
+
+    while let Some(parent_id) = parent {
+        parent = get_with_id(db, parent_id)?.parent_id;
+        depth += 1;
+    }
+
+    Ok(depth)
+}
+
+/// Checks that `parent` is an approved comment on the given post that may still be replied to.
+fn check_parent(db: &DbConn, post: i32, parent: i32) -> Result<(), HashMap<String, String>> {
+    let parent = get_with_id(db, parent)
+        .ok()
+        .and_then(|parent| {
+            if parent.post_id == post && parent.status == CommentStatus::Approved.as_str() {
+                Some(parent)
+            } else {
+                None
+            }
+        })
+        .ok_or_else(|| convert_args!(hashmap!(
+            "parent" => "The comment you replied to doesn't exist.",
+        )))?;
+
+    match depth(db, &parent) {
+        Ok(depth) if depth < MAX_DEPTH => Ok(()),
+        Ok(_) => Err(convert_args!(hashmap!(
+            "parent" => "Replies can't be nested any deeper.",
+        ))),
+        Err(err) => {
+            // TODO add real logging here
+            println!("Error loading parent comments: {:?}", err);
+
+            Err(convert_args!(hashmap!(
+                "general" => "Error saving your comment. Please try again later.",
+            )))
+        },
+    }
+}
+
+/// Loads all comments with the given status, newest first.
+pub fn with_status(db: &DbConn, comment_status: CommentStatus) -> QueryResult<Vec<Comment>> {
+    use diesel::prelude::*;
+    use db::schema::comments::dsl::*;
+
+    comments
+        .filter(status.eq(comment_status.as_str()))
+        .order(created_at.desc())
+        .load::<Comment>(&**db)
+}
+
+/// Saves a new comment on the given post. New comments are pending until a moderator
+/// approves them.
+pub fn try_insert(db: &DbConn, post: i32, comment: &NewComment) -> Result<Comment, HashMap<String, String>> {
+    let errors = comment.errors();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    if let Some(parent) = comment.parent {
+        check_parent(db, post, parent)?;
+    }
+
+    // this is all safe to unwrap, because we got no errors.
+    let comment = comment.clone();
+    let new_comment = NewDbComment {
+        post_id: post,
+        parent_id: comment.parent,
+        author_name: comment.author_name.unwrap().into_inner(),
+        author_email: comment.author_email.unwrap().into_inner(),
+        body: comment.body.unwrap().into_inner(),
+        status: CommentStatus::Pending.as_str().to_string(),
+    };
+
+    diesel::insert(&new_comment).into(comments::table)
+        .execute(&**db) // TODO use get_result with non sqlite database
+        .and_then(|_| {
+            use diesel::prelude::*;
+            use db::schema::comments::dsl::*;
+
+            comments.order(id.desc()).first(&**db)
+        })
+        .map_err(|err| {
+            // TODO add real logging here
+            println!("Error inserting comment: {:?}", err);
+
+            convert_args!(hashmap!(
+                "general" => "Error saving your comment. Please try again later.",
+            ))
+        })
+}
+
+pub fn set_status(db: &DbConn, comment_id: i32, comment_status: CommentStatus) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::comments::dsl::*;
+
+    diesel::update(comments.find(comment_id))
+        .set(status.eq(comment_status.as_str()))
+        .execute(&**db)
+}
+
+/// Deletes the given comment. Its replies are moved up one level, so they are kept.
+pub fn delete(db: &DbConn, comment_id: i32) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::comments::dsl::*;
+
+    let comment = get_with_id(db, comment_id)?;
+    diesel::update(comments.filter(parent_id.eq(comment_id)))
+        .set(parent_id.eq(comment.parent_id))
+        .execute(&**db)?;
+    diesel::delete(comments.find(comment_id)).execute(&**db)
+}
+
+/// Deletes all comments of the given post.
+pub fn remove_from_post(db: &DbConn, post: i32) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::comments::dsl::*;
+
+    // replies reference their parents, so unlink them before deleting anything
+    diesel::update(comments.filter(post_id.eq(post)))
+        .set(parent_id.eq(None::<i32>))
+        .execute(&**db)?;
+    diesel::delete(comments.filter(post_id.eq(post))).execute(&**db)
+}
+
+#[derive(Debug, Clone, FromForm)]
+pub struct NewComment {
+    pub author_name: Result<NonEmpty, &'static str>,
+    pub author_email: Result<NonEmpty, &'static str>,
+    pub body: Result<NonEmpty, &'static str>,
+    /// Id of the comment this is a reply to.
+    pub parent: Option<i32>,
+}
+
+impl NewComment {
+    pub fn errors(&self) -> HashMap<String, String> {
+        let mut m = HashMap::new();
+
+        if let Err(e) = self.author_name {
+            m.insert("author_name".to_string(), format!("Name {}.", e));
+        }
+        match self.author_email {
+            Err(e) => {
+                m.insert("author_email".to_string(), format!("Email {}.", e));
+            },
+            Ok(ref email) if !email.clone().into_inner().contains('@') => {
+                m.insert("author_email".to_string(), "Email is not a valid email address.".to_string());
+            },
+            Ok(_) => {},
+        }
+        if let Err(e) = self.body {
+            m.insert("body".to_string(), format!("Comment {}.", e));
+        }
+
+        m
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Insertable)]
+#[table_name="comments"]
+struct NewDbComment {
+    post_id: i32,
+    parent_id: Option<i32>,
+    author_name: String,
+    author_email: String,
+    body: String,
+    status: String,
+}
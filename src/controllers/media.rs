@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use chrono::{NaiveDateTime, Utc};
+use diesel;
+use diesel::prelude::*;
+
+use config::UploadConfig;
+use db::models::{Media, MediaVariant, User};
+use db::schema::{media, media_variants};
+use db::DbConn;
+use forms::media::MediaUpload;
+use markdown::images::ResponsiveImage;
+use media::{sniff, extension, resize, Resized};
+use routes::Urlify;
+use slug;
+
+/// Loads all files of the media library, newest first.
+pub fn all(db: &DbConn) -> QueryResult<Vec<Media>> {
+    use diesel::prelude::*;
+    use db::schema::media::dsl::*;
+
+    media.order(uploaded_at.desc()).load::<Media>(&**db)
+}
+
+pub fn get_with_id(db: &DbConn, media_id: i32) -> QueryResult<Media> {
+    use diesel::prelude::*;
+    use db::schema::media::dsl::*;
+
+    media.find(media_id).first(&**db)
+}
+
+fn get_with_filename(db: &DbConn, name: &str) -> QueryResult<Media> {
+    use diesel::prelude::*;
+    use db::schema::media::dsl::*;
+
+    media.filter(filename.eq(name)).first(&**db)
+}
+
+/// Loads the scaled down copies of the given file, narrowest first.
+pub fn variants(db: &DbConn, file: i32) -> QueryResult<Vec<MediaVariant>> {
+    use diesel::prelude::*;
+    use db::schema::media_variants::dsl::*;
+
+    media_variants.filter(media_id.eq(file)).order(width.asc()).load::<MediaVariant>(&**db)
+}
+
+/// Loads what is needed to render the image at `url` with `srcset`, if it belongs to the media
+/// library and its size is known.
+pub fn responsive_image(db: &DbConn, url: &str) -> Option<ResponsiveImage> {
+    let prefix = format!("{}/", Media::BASE_URL);
+    if !url.starts_with(&prefix) {
+        return None;
+    }
+    let name = &url[prefix.len()..];
+
+    let file = get_with_filename(db, name).ok()?;
+    let sources = variants(db, file.id).ok()?
+        .into_iter()
+        .map(|variant| (variant.url(), variant.width as u32))
+        .collect();
+
+    Some(ResponsiveImage {
+        url: file.url(),
+        width: file.width? as u32,
+        height: file.height? as u32,
+        sources,
+    })
+}
+
+fn filename_exists(db: &DbConn, name: &str) -> QueryResult<bool> {
+    use diesel::prelude::*;
+    use db::schema::media::dsl::*;
+
+    media.filter(filename.eq(name)).count().get_result::<i64>(&**db).map(|count| count > 0)
+}
+
+/// Checks the uploaded file, stores it in the uploads directory and adds it to the media library.
+/// The type is detected from the content of the file, the name is only used to derive a
+/// readable, unique filename.
+pub fn try_insert(db: &DbConn, config: &UploadConfig, upload: &MediaUpload, uploader: &User) -> Result<Media, HashMap<String, String>> {
+    let changes = validate(db, config, upload, uploader)?;
+    let path = Path::new(&config.directory).join(&changes.filename);
+
+    store(&path, &upload.data)
+        .map_err(|err| {
+            // TODO add real logging here
+            println!("Error storing upload {:?}: {:?}", path, err);
+
+            convert_args!(hashmap!(
+                "general" => "Error saving the file. Please try again later.",
+            ))
+        })?;
+
+    let file = diesel::insert(&changes).into(media::table)
+        .execute(&**db) // TODO use get_result with non sqlite database
+        .and_then(|_| get_with_filename(db, &changes.filename))
+        .map_err(|err| {
+            // TODO add real logging here
+            println!("Error inserting media: {:?}", err);
+            let _ = fs::remove_file(&path);
+
+            convert_args!(hashmap!(
+                "general" => "Error saving the file. Please try again later.",
+            ))
+        })?;
+
+    // the original is usable without the size and the variants, so errors are only logged
+    let resized = match resize(&path, &upload.data, &file.mime_type, &config.variants, config.max_pixels) {
+        Ok(resized) => resized,
+        Err(err) => {
+            // TODO add real logging here
+            println!("Error resizing {:?}: {:?}", path, err);
+            return Ok(file);
+        },
+    };
+
+    Ok(add_variants(db, &file, &resized).unwrap_or_else(|err| {
+        // TODO add real logging here
+        println!("Error saving image variants: {:?}", err);
+        file
+    }))
+}
+
+/// Stores the size of the image and its scaled down copies.
+fn add_variants(db: &DbConn, file: &Media, resized: &Resized) -> QueryResult<Media> {
+    use diesel::prelude::*;
+    use db::schema::media::dsl::*;
+
+    diesel::update(media.find(file.id))
+        .set((width.eq(Some(resized.width as i32)), height.eq(Some(resized.height as i32))))
+        .execute(&**db)?;
+
+    let new_variants: Vec<NewMediaVariant> = resized.variants.iter()
+        .map(|variant| NewMediaVariant {
+            media_id: file.id,
+            name: variant.name.clone(),
+            filename: variant.filename.clone(),
+            width: variant.width as i32,
+            height: variant.height as i32,
+        })
+        .collect();
+    if !new_variants.is_empty() {
+        diesel::insert(&new_variants).into(media_variants::table).execute(&**db)?;
+    }
+
+    get_with_id(db, file.id)
+}
+
+fn validate(db: &DbConn, config: &UploadConfig, upload: &MediaUpload, uploader: &User) -> Result<MediaChanges, HashMap<String, String>> {
+    if upload.data.is_empty() {
+        return Err(convert_args!(hashmap!(
+            "file" => "Please choose a file.",
+        )));
+    }
+
+    if upload.data.len() as u64 > config.max_size {
+        return Err(convert_args!(hashmap!(
+            "file" => format!("The file is larger than {} KB.", config.max_size / 1024),
+        )));
+    }
+
+    let (mime_type, ext) = match sniff(&upload.data).and_then(|mime| Some((mime, extension(mime)?))) {
+        Some((mime, ext)) if config.types.iter().any(|allowed| allowed == mime) => (mime, ext),
+        _ => return Err(convert_args!(hashmap!(
+            "file" => format!("Only {} files can be uploaded.", config.types.join(", ")),
+        ))),
+    };
+
+    let stem = match upload.filename.rfind('.') {
+        Some(dot) if dot > 0 => &upload.filename[..dot],
+        _ => upload.filename.as_str(),
+    };
+    let mut stem = slug::slugify(stem);
+    if stem.is_empty() {
+        stem = "upload".to_string();
+    }
+    let stem = slug::unique(&stem, |candidate| filename_exists(db, &format!("{}.{}", candidate, ext)))
+        .map_err(|err| {
+            // TODO add real logging here
+            println!("Error checking filename: {:?}", err);
+
+            convert_args!(hashmap!(
+                "general" => "Error saving the file. Please try again later.",
+            ))
+        })?;
+
+    Ok(MediaChanges {
+        filename: format!("{}.{}", stem, ext),
+        original_name: upload.filename.clone(),
+        mime_type: mime_type.to_string(),
+        size: upload.data.len() as i32,
+        uploader_id: Some(uploader.id),
+        uploaded_at: Utc::now().naive_utc(),
+    })
+}
+
+/// Writes a new file, never overwriting an existing one.
+fn store(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    OpenOptions::new().write(true).create_new(true).open(path)?.write_all(data)
+}
+
+/// Removes the file and its scaled down copies from the media library and the uploads directory.
+/// Posts that still embed the file are not changed.
+pub fn delete(db: &DbConn, config: &UploadConfig, file: &Media) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::media::dsl::*;
+
+    let copies = variants(db, file.id)?;
+    diesel::delete(media_variants::table.filter(media_variants::media_id.eq(file.id))).execute(&**db)?;
+    let deleted = diesel::delete(media.find(file.id)).execute(&**db)?;
+
+    let filenames = copies.iter().map(|variant| &variant.filename).chain(Some(&file.filename));
+    for name in filenames {
+        let path = Path::new(&config.directory).join(name);
+        match fs::remove_file(&path) {
+            Err(ref err) if err.kind() != io::ErrorKind::NotFound => {
+                // TODO add real logging here
+                println!("Error removing upload {:?}: {:?}", path, err);
+            },
+            _ => {},
+        }
+    }
+
+    Ok(deleted)
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Insertable)]
+#[table_name="media"]
+struct MediaChanges {
+    filename: String,
+    original_name: String,
+    mime_type: String,
+    size: i32,
+    uploader_id: Option<i32>,
+    uploaded_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Insertable)]
+#[table_name="media_variants"]
+struct NewMediaVariant {
+    media_id: i32,
+    name: String,
+    filename: String,
+    width: i32,
+    height: i32,
+}
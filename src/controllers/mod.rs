@@ -1 +1,12 @@
-pub mod posts;
\ No newline at end of file
+#[macro_use]
+mod terms;
+
+pub mod posts;
+pub mod pages;
+pub mod pagination;
+pub mod users;
+pub mod tags;
+pub mod categories;
+pub mod comments;
+pub mod media;
+pub mod search;
\ No newline at end of file
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use chrono::{NaiveDateTime, Utc};
+use diesel;
+use diesel::prelude::*;
+use rocket::Outcome;
+use rocket::request::{self, Request, FromRequest};
+
+use db::models::Page;
+use db::schema::pages;
+use db::DbConn;
+use forms::NonEmpty;
+use slug;
+
+/// First path segments that are used by other routes and can't be used as slugs of top level
+/// pages.
+const RESERVED_SLUGS: [&'static str; 11] = [
+    "admin", "post", "page", "tag", "category", "archive", "login", "logout", "pages", "search",
+    "media",
+];
+
+/// Loads all pages ordered by path, so sub pages directly follow their parent.
+pub fn all(db: &DbConn) -> QueryResult<Vec<Page>> {
+    use diesel::prelude::*;
+    use db::schema::pages::dsl::*;
+
+    pages.order(path.asc()).load::<Page>(&**db)
+}
+
+pub fn get_with_id(db: &DbConn, page_id: i32) -> QueryResult<Page> {
+    use diesel::prelude::*;
+    use db::schema::pages::dsl::*;
+
+    pages.find(page_id).first(&**db)
+}
+
+/// Loads the pages with the given ids, in no particular order.
+pub fn with_ids(db: &DbConn, ids: Vec<i32>) -> QueryResult<Vec<Page>> {
+    use diesel::prelude::*;
+    use db::schema::pages::dsl::*;
+
+    pages.filter(id.eq_any(ids)).load::<Page>(&**db)
+}
+
+pub fn get_with_path(db: &DbConn, page_path: &str) -> QueryResult<Page> {
+    use diesel::prelude::*;
+    use db::schema::pages::dsl::*;
+
+    pages.filter(path.eq(page_path)).first(&**db)
+}
+
+/// Loads the direct children of the given page ordered by title.
+pub fn children(db: &DbConn, page: i32) -> QueryResult<Vec<Page>> {
+    use diesel::prelude::*;
+    use db::schema::pages::dsl::*;
+
+    pages.filter(parent_id.eq(page)).order(title.asc()).load::<Page>(&**db)
+}
+
+/// Loads all ancestors of the given page, starting with the top level page.
+pub fn ancestors(db: &DbConn, page: &Page) -> QueryResult<Vec<Page>> {
+    use diesel::prelude::*;
+    use db::schema::pages::dsl::*;
+
+    let segments: Vec<&str> = page.path.split('/').collect();
+    let paths: Vec<String> = (1..segments.len())
+        .map(|len| segments[..len].join("/"))
+        .collect();
+
+    let mut ancestors = pages.filter(path.eq_any(paths)).load::<Page>(&**db)?;
+    ancestors.sort_by_key(|ancestor| ancestor.path.len());
+    Ok(ancestors)
+}
+
+/// Loads all pages that can become the parent of the given page, i.e. all pages except the page
+/// itself and its descendants.
+pub fn possible_parents(db: &DbConn, page: Option<&Page>) -> QueryResult<Vec<Page>> {
+    let pages = all(db)?;
+
+    Ok(match page {
+        Some(page) => pages.into_iter().filter(|other| !is_same_or_descendant(other, page)).collect(),
+        None => pages,
+    })
+}
+
+fn is_same_or_descendant(page: &Page, ancestor: &Page) -> bool {
+    page.id == ancestor.id || page.path.starts_with(&format!("{}/", ancestor.path))
+}
+
+pub fn try_insert(db: &DbConn, form: &NewPage) -> Result<Page, HashMap<String, String>> {
+    let changes = validate(db, form, None)?;
+
+    diesel::insert(&changes).into(pages::table)
+        .execute(&**db) // TODO use get_result with non sqlite database
+        .and_then(|_| get_with_path(db, &changes.path))
+        .map_err(|err| {
+            // TODO add real logging here
+            println!("Error inserting page: {:?}", err);
+
+            convert_args!(hashmap!(
+                "general" => "Error saving the page. Please try again later.",
+            ))
+        })
+}
+
+pub fn try_update(db: &DbConn, page: &Page, form: &NewPage) -> Result<Page, HashMap<String, String>> {
+    let changes = validate(db, form, Some(page))?;
+
+    update_page(db, page.id, &changes)
+        .and_then(|updated| {
+            move_descendants(db, &page.path, &updated.path).map(|_| updated)
+        })
+        .map_err(|err| {
+            // TODO add real logging here
+            println!("Error updating page: {:?}", err);
+
+            convert_args!(hashmap!(
+                "general" => "Error saving the page. Please try again later.",
+            ))
+        })
+}
+
+fn update_page(db: &DbConn, page_id: i32, changes: &PageChanges) -> QueryResult<Page> {
+    use diesel::prelude::*;
+    use db::schema::pages::dsl::*;
+
+    diesel::update(pages.find(page_id)).set(changes).execute(&**db)?;
+    get_with_id(db, page_id)
+}
+
+/// Updates the paths of all descendants after their ancestor moved from `old_path` to `new_path`.
+fn move_descendants(db: &DbConn, old_path: &str, new_path: &str) -> QueryResult<()> {
+    use diesel::prelude::*;
+    use db::schema::pages::dsl::*;
+
+    if old_path == new_path {
+        return Ok(());
+    }
+
+    let prefix = format!("{}/", old_path);
+    let descendants = pages.filter(path.like(format!("{}%", prefix))).load::<Page>(&**db)?;
+
+    for descendant in descendants {
+        let moved = format!("{}/{}", new_path, &descendant.path[prefix.len()..]);
+        diesel::update(pages.find(descendant.id)).set(path.eq(moved)).execute(&**db)?;
+    }
+
+    Ok(())
+}
+
+/// Deletes the given page.
+/// Pages with sub pages can't be deleted, so this returns `Ok(0)` for them.
+pub fn delete(db: &DbConn, page_id: i32) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::pages::dsl::*;
+
+    if !children(db, page_id)?.is_empty() {
+        return Ok(0);
+    }
+
+    diesel::delete(pages.find(page_id)).execute(&**db)
+}
+
+/// Validates the form, builds the path from the parent and the slug and checks that it is
+/// neither reserved nor used by another page than `current`.
+fn validate(db: &DbConn, form: &NewPage, current: Option<&Page>) -> Result<PageChanges, HashMap<String, String>> {
+    let mut errors = form.errors();
+
+    let parent = match form.parent {
+        Some(parent_id) => match get_with_id(db, parent_id) {
+            Ok(ref parent) if current.map_or(false, |page| is_same_or_descendant(parent, page)) => {
+                errors.insert("parent".to_string(), "A page can't be moved below itself.".to_string());
+                None
+            },
+            Ok(parent) => Some(parent),
+            Err(_) => {
+                errors.insert("parent".to_string(), "Parent page doesn't exist.".to_string());
+                None
+            },
+        },
+        None => None,
+    };
+
+    let page_slug = match form.title {
+        Ok(ref title) if form.slug.trim().is_empty() => slug::slugify(&title.clone().into_inner()),
+        _ => slug::slugify(&form.slug),
+    };
+    let page_path = match parent {
+        Some(ref parent) => format!("{}/{}", parent.path, page_slug),
+        None => page_slug.clone(),
+    };
+
+    if page_slug.is_empty() && !errors.contains_key("title") {
+        errors.insert("slug".to_string(), "Slug can't be empty.".to_string());
+    } else if parent.is_none() && RESERVED_SLUGS.contains(&page_slug.as_str()) {
+        errors.insert("slug".to_string(), format!("Slug \"{}\" is reserved.", page_slug));
+    } else if let Ok(other) = get_with_path(db, &page_path) {
+        if Some(other.id) != current.map(|page| page.id) {
+            errors.insert("slug".to_string(), "Slug is already used by another page.".to_string());
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // this is all safe to unwrap, because we got no errors.
+    let form = form.clone();
+
+    Ok(PageChanges {
+        title: form.title.unwrap().into_inner(),
+        slug: page_slug,
+        body: form.body.unwrap().into_inner(),
+        updated_at: Utc::now().naive_utc(),
+        parent_id: parent.map(|parent| parent.id),
+        path: page_path,
+    })
+}
+
+/// Retrieves the page whose path matches the request path.
+/// Forwards if there is no such page, so the request can still be handled by routes with a
+/// higher rank (like the static files).
+impl<'a, 'r> FromRequest<'a, 'r> for Page {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Page, ()> {
+        let db = request.guard::<DbConn>()?;
+        let path = request.uri().path().trim_matches('/');
+
+        match get_with_path(&db, path) {
+            Ok(page) => Outcome::Success(page),
+            Err(_) => Outcome::Forward(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromForm)]
+pub struct NewPage {
+    pub title: Result<NonEmpty, &'static str>,
+    /// Generated from the title if left empty.
+    pub slug: String,
+    pub body: Result<NonEmpty, &'static str>,
+    /// Id of the parent page, empty for top level pages.
+    pub parent: Option<i32>,
+}
+
+impl NewPage {
+    pub fn errors(&self) -> HashMap<String, String> {
+        let mut m = HashMap::new();
+
+        if let Err(e) = self.title {
+            m.insert("title".to_string(), format!("Title {}.", e));
+        }
+        if let Err(e) = self.body {
+            m.insert("body".to_string(), format!("Body {}.", e));
+        }
+
+        m
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Insertable, AsChangeset)]
+#[table_name="pages"]
+#[changeset_options(treat_none_as_null = "true")]
+struct PageChanges {
+    title: String,
+    slug: String,
+    body: String,
+    updated_at: NaiveDateTime,
+    parent_id: Option<i32>,
+    path: String,
+}
@@ -0,0 +1,74 @@
+/// Number of posts shown on one listing page.
+pub const PER_PAGE: i64 = 5;
+
+/// A single page of items together with the [`Pagination`] used to load them.
+///
+/// [`Pagination`]: struct.Pagination.html
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub pagination: Pagination,
+}
+
+/// Holds everything a template needs to render the pagination of a listing.
+/// Pages start at `1`, and the first page is always served from the `base_url`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct Pagination {
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+    pub total_pages: i64,
+    pub prev_url: Option<String>,
+    pub next_url: Option<String>,
+}
+
+impl Pagination {
+    /// Creates a new `Pagination` for the listing at `base_url` with `total` items.
+    pub fn new(base_url: &str, page: i64, per_page: i64, total: i64) -> Pagination {
+        let total_pages = if total <= 0 {
+            1
+        } else {
+            (total + per_page - 1) / per_page
+        };
+
+        let prev_url = if page > 1 {
+            Some(page_url(base_url, page - 1))
+        } else {
+            None
+        };
+        let next_url = if page < total_pages {
+            Some(page_url(base_url, page + 1))
+        } else {
+            None
+        };
+
+        Pagination {
+            page,
+            per_page,
+            total,
+            total_pages,
+            prev_url,
+            next_url,
+        }
+    }
+
+    /// Returns `true` if `page` is one of the pages of this listing.
+    pub fn is_valid(&self) -> bool {
+        self.page >= 1 && self.page <= self.total_pages
+    }
+
+    /// The number of items to skip to get to the current page.
+    pub fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
+}
+
+/// Returns the url of `page` for the listing at `base_url`.
+/// The first page is the `base_url` itself, all other pages are at `<base_url>/page/<page>`.
+pub fn page_url(base_url: &str, page: i64) -> String {
+    if page <= 1 {
+        base_url.to_string()
+    } else {
+        format!("{}/page/{}", base_url.trim_right_matches('/'), page)
+    }
+}
@@ -1,28 +1,179 @@
 use std::collections::HashMap;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Utc};
 use diesel;
 use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use diesel::helper_types::{And, EqAny, LtEq};
 
-use db::models::Post;
+use db::models::{Post, PostStatus, User, Tag, Category};
 use db::schema::posts;
 use db::DbConn;
-use forms::NonEmpty;
+use auth::permissions::{Permission, PublishPost, Role};
+use controllers::pagination::{Paginated, Pagination, PER_PAGE};
+use controllers::{tags, categories, comments};
+use forms::{self, NonEmpty, OptionalDateTime};
+use slug;
+use routes::Urlify;
+use forms::posts::PostFilter;
+use markdown::Renderer;
 
-pub fn posts(db: &DbConn) -> Vec<Post> {
+/// Returns the statuses of posts that are visible to the public once `published_at` has passed.
+fn public_statuses() -> Vec<&'static str> {
+    PostStatus::PUBLIC.iter().map(|s| s.as_str()).collect()
+}
+
+/// Returns the condition for posts that are visible to the public.
+/// These are published or scheduled posts whose `published_at` has passed.
+pub fn is_visible() -> And<EqAny<posts::status, Vec<&'static str>>, LtEq<posts::published_at, NaiveDateTime>> {
+    use db::schema::posts::dsl::*;
+
+    status.eq_any(public_statuses()).and(published_at.le(Utc::now().naive_utc()))
+}
+
+/// Returns a query for all posts that are visible to the public.
+fn visible<'a>() -> posts::BoxedQuery<'a, Sqlite> {
+    use db::schema::posts::dsl::*;
+
+    posts.filter(is_visible()).into_boxed()
+}
+
+/// Loads the given `page` of published posts, newest first.
+/// Returns `NotFound` if the page does not exist.
+pub fn paginated(db: &DbConn, page: i64) -> QueryResult<Paginated<Post>> {
+    paginate(db, "/", page, visible)
+}
+
+/// Loads the given `page` of published posts with the given tag, newest first.
+pub fn paginated_for_tag(db: &DbConn, tag: &Tag, page: i64) -> QueryResult<Paginated<Post>> {
+    paginate(db, &tag.url(), page, || tags::filter_posts(visible(), tag.id))
+}
+
+/// Loads the given `page` of published posts in the given category, newest first.
+pub fn paginated_for_category(db: &DbConn, category: &Category, page: i64) -> QueryResult<Paginated<Post>> {
+    paginate(db, &category.url(), page, || categories::filter_posts(visible(), category.id))
+}
+
+/// Loads the given `page` of published posts between `start` (inclusive) and `end` (exclusive),
+/// newest first.
+pub fn paginated_for_period(db: &DbConn, base_url: &str, start: NaiveDateTime, end: NaiveDateTime, page: i64) -> QueryResult<Paginated<Post>> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    paginate(db, base_url, page, || {
+        visible()
+            .filter(published_at.ge(start))
+            .filter(published_at.lt(end))
+    })
+}
+
+/// Loads the visible posts among the given ids, in no particular order.
+pub fn visible_with_ids(db: &DbConn, ids: Vec<i32>) -> QueryResult<Vec<Post>> {
     use diesel::prelude::*;
     use db::schema::posts::dsl::*;
 
-    posts.filter(published_at.is_not_null())
+    visible().filter(id.eq_any(ids)).load::<Post>(&**db)
+}
+
+/// Loads the `count` most recently published posts.
+pub fn recent(db: &DbConn, count: i64) -> QueryResult<Vec<Post>> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    visible()
+        .order(published_at.desc())
+        .limit(count)
+        .load::<Post>(&**db)
+}
+
+/// Loads the `count` most recently published posts with the given tag.
+pub fn recent_for_tag(db: &DbConn, tag: &Tag, count: i64) -> QueryResult<Vec<Post>> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    tags::filter_posts(visible(), tag.id)
         .order(published_at.desc())
-        .limit(5)
+        .limit(count)
         .load::<Post>(&**db)
-        .expect("error loading posts")
+}
+
+/// Loads the `count` published posts with the most views.
+pub fn popular(db: &DbConn, count: i64) -> QueryResult<Vec<Post>> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    visible()
+        .order(views.desc())
+        .limit(count)
+        .load::<Post>(&**db)
+}
+
+/// Counts one more view of the given post.
+pub fn add_view(db: &DbConn, post_id: i32) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    diesel::update(posts.find(post_id))
+        .set(views.eq(views + 1))
+        .execute(&**db)
+}
+
+/// Returns every month with published posts, newest first.
+pub fn archive_months(db: &DbConn) -> QueryResult<Vec<ArchiveMonth>> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    let dates = posts.filter(is_visible())
+        .select(published_at)
+        .order(published_at.desc())
+        .load::<Option<NaiveDateTime>>(&**db)?;
+
+    let mut months: Vec<ArchiveMonth> = Vec::new();
+    for date in dates.into_iter().filter_map(|date| date) {
+        let (year, month) = (date.year(), date.month());
+        if let Some(last) = months.last_mut() {
+            if last.year == year && last.month == month {
+                last.count += 1;
+                continue;
+            }
+        }
+        months.push(ArchiveMonth { year, month, count: 1 });
+    }
+
+    Ok(months)
+}
+
+/// Loads the given `page` of the posts returned by `query`, newest first.
+/// `query` is called once for counting and once for loading the posts.
+fn paginate<'a, F>(db: &DbConn, base_url: &str, page: i64, query: F) -> QueryResult<Paginated<Post>>
+where
+    F: Fn() -> posts::BoxedQuery<'a, Sqlite>,
+{
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    let total = query()
+        .count()
+        .get_result::<i64>(&**db)?;
+
+    let pagination = Pagination::new(base_url, page, PER_PAGE, total);
+    if !pagination.is_valid() {
+        return Err(diesel::result::Error::NotFound);
+    }
+
+    let items = query()
+        .order(published_at.desc())
+        .limit(pagination.per_page)
+        .offset(pagination.offset())
+        .load::<Post>(&**db)?;
+
+    Ok(Paginated { items, pagination })
 }
 
 pub fn get_with_id(db: &DbConn, post_id: i32) -> QueryResult<Post> {
     use diesel::prelude::*;
     use db::schema::posts::dsl::*;
 
-    posts.filter(published_at.is_not_null())
+    visible()
         .filter(id.eq(post_id))
         .first(&**db)
 }
@@ -31,23 +182,33 @@ pub fn get_with_slug(db: &DbConn, post_slug: String) -> QueryResult<Post> {
     use diesel::prelude::*;
     use db::schema::posts::dsl::*;
 
-    posts.filter(published_at.is_not_null())
+    visible()
         .filter(slug.eq(post_slug))
         .first(&**db)
 }
 
-pub fn try_insert(db: &DbConn, post: &NewPost) -> Result<Post, HashMap<String, String>> {
-    let post: Result<NewDbPost, HashMap<String, String>> = post.into();
+pub fn try_insert(db: &DbConn, renderer: &Renderer, post: &NewPost, author: &User) -> Result<Post, HashMap<String, String>> {
+    let changes: Result<PostChanges, HashMap<String, String>> = post.into();
 
-    post.and_then(|post| {
-        insert_post(&db, &post).map_err(|err| {
-            // TODO add real logging here
-            println!("Error inserting post: {:?}", err);
+    changes.and_then(|changes| {
+        check_status(&changes, PostStatus::Draft, author)?;
+        let changes = changes.render(db, renderer)?;
+        unique_slug(&db, &changes.title)
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|slug| insert_post(&db, &NewDbPost::new(changes, slug, author)))
+            .and_then(|inserted| {
+                save_terms(&db, inserted.id, post)
+                    .map(|_| inserted)
+                    .map_err(|err| format!("{:?}", err))
+            })
+            .map_err(|err| {
+                // TODO add real logging here
+                println!("Error inserting post: {:?}", err);
 
-            convert_args!(hashmap!(
-                "general" => "Error saving your post. Please try again later.",
-            ))
-        })
+                convert_args!(hashmap!(
+                    "general" => "Error saving your post. Please try again later.",
+                ))
+            })
     })
 }
 
@@ -71,11 +232,215 @@ fn insert_post(db: &DbConn, post: &NewDbPost) -> Result<Post, String> {
         })
 }
 
+/// Loads all posts regardless of their status, newest first.
+/// Used by the backend, so the `filter` can narrow the list down by status and author.
+/// Authors only get their own posts.
+pub fn all(db: &DbConn, filter: &PostFilter, user: &User) -> QueryResult<Vec<Post>> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    let mut query = posts.into_boxed();
+
+    if user.role() == Role::Author {
+        query = query.filter(author_id.eq(user.id));
+    }
+
+    if let Some(filter_status) = filter.status() {
+        query = query.filter(status.eq(filter_status.as_str()));
+    }
+    if let Some(filter_author) = filter.author() {
+        query = query.filter(author.eq(filter_author.to_string()));
+    }
+
+    query.order(id.desc()).load::<Post>(&**db)
+}
+
+/// Loads the post with the given id regardless of its status.
+pub fn get_any_with_id(db: &DbConn, post_id: i32) -> QueryResult<Post> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    posts.find(post_id).first(&**db)
+}
+
+pub fn try_update(db: &DbConn, renderer: &Renderer, post: &Post, form: &NewPost, editor: &User) -> Result<Post, HashMap<String, String>> {
+    let post_id = post.id;
+    let changes: Result<PostChanges, HashMap<String, String>> = form.into();
+
+    changes.and_then(|changes| {
+        check_status(&changes, post.status(), editor)?;
+        let changes = changes.render(db, renderer)?;
+        update_post(&db, post_id, &changes)
+            .and_then(|updated| save_terms(&db, post_id, form).map(|_| updated))
+            .map_err(|err| {
+                // TODO add real logging here
+                println!("Error updating post: {:?}", err);
+
+                convert_args!(hashmap!(
+                    "general" => "Error saving your post. Please try again later.",
+                ))
+            })
+    })
+}
+
+/// Renders the markdown of all posts again, e.g. after the markdown or sanitizer options were
+/// changed. Posts whose shortcodes can't be rendered anymore keep their old html.
+/// Returns the number of updated posts.
+pub fn rerender_all(db: &DbConn, renderer: &Renderer) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    let all_posts = posts.load::<Post>(&**db)?;
+    rerender(db, renderer, &all_posts)
+}
+
+/// Renders the posts without html, e.g. the ones written before the html was stored.
+/// Returns the number of updated posts.
+pub fn render_missing(db: &DbConn, renderer: &Renderer) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    let missing = posts.filter(body.ne(""))
+        .filter(body_html.eq("").or(excerpt_html.eq("")))
+        .load::<Post>(&**db)?;
+    rerender(db, renderer, &missing)
+}
+
+fn rerender(db: &DbConn, renderer: &Renderer, changed: &[Post]) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    let mut count = 0;
+
+    for post in changed {
+        let rendered = renderer.render_with_shortcodes(db, &post.body)
+            .and_then(|rendered| {
+                renderer.render_excerpt(db, &post.excerpt, &post.body).map(|excerpt| (rendered, excerpt))
+            });
+
+        match rendered {
+            Ok((rendered, excerpt)) => {
+                diesel::update(posts.find(post.id))
+                    .set((body_html.eq(rendered.html), toc_html.eq(rendered.toc), excerpt_html.eq(excerpt)))
+                    .execute(&**db)?;
+                count += 1;
+            },
+            Err(errors) => {
+                // TODO add real logging here
+                println!("Error rendering post {}: {}", post.id, errors.join(" "));
+            },
+        }
+    }
+
+    Ok(count)
+}
+
+/// A month with published posts.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct ArchiveMonth {
+    pub year: i32,
+    pub month: u32,
+    pub count: i64,
+}
+
+impl ArchiveMonth {
+    /// Returns the url of the archive page of this month.
+    pub fn url(&self) -> String {
+        format!("/archive/{}/{:02}", self.year, self.month)
+    }
+
+    /// Returns the name of this month, e.g. "March 2013".
+    pub fn name(&self) -> String {
+        NaiveDate::from_ymd(self.year, self.month, 1).format("%B %Y").to_string()
+    }
+}
+
+/// Replaces the tags and categories of the post with the ones entered in the form.
+fn save_terms(db: &DbConn, post_id: i32, form: &NewPost) -> QueryResult<()> {
+    tags::set_for_post(db, post_id, &forms::parse_list(&form.tags))?;
+    categories::set_for_post(db, post_id, &forms::parse_list(&form.categories))
+}
+
+/// Checks that the user is allowed to move a post from the `current` status to the one in
+/// `changes`. Users without the [`PublishPost`] permission may only keep the current status or
+/// save drafts.
+///
+/// [`PublishPost`]: ../../auth/permissions/struct.PublishPost.html
+fn check_status(changes: &PostChanges, current: PostStatus, user: &User) -> Result<(), HashMap<String, String>> {
+    let allowed = PublishPost::is_granted(user.role())
+        || changes.status == PostStatus::Draft.as_str()
+        || changes.status == current.as_str();
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(convert_args!(hashmap!(
+            "status" => "You are not allowed to publish posts.",
+        )))
+    }
+}
+
+fn update_post(db: &DbConn, post_id: i32, changes: &PostChanges) -> QueryResult<Post> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    diesel::update(posts.find(post_id)).set(changes).execute(&**db)?;
+    get_any_with_id(db, post_id)
+}
+
+/// Publishes the post with the given id.
+/// Keeps the existing publish date if it lies in the past, otherwise the post is published now.
+pub fn publish(db: &DbConn, post_id: i32) -> QueryResult<Post> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    let post = get_any_with_id(db, post_id)?;
+    let now = Utc::now().naive_utc();
+    let date = match post.published_at {
+        Some(date) if date <= now => date,
+        _ => now,
+    };
+
+    diesel::update(posts.find(post_id))
+        .set((status.eq(PostStatus::Published.as_str()), published_at.eq(Some(date))))
+        .execute(&**db)?;
+    get_any_with_id(db, post_id)
+}
+
+/// Turns the post with the given id back into a draft.
+pub fn unpublish(db: &DbConn, post_id: i32) -> QueryResult<Post> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    let no_date: Option<NaiveDateTime> = None;
+    diesel::update(posts.find(post_id))
+        .set((status.eq(PostStatus::Draft.as_str()), published_at.eq(no_date)))
+        .execute(&**db)?;
+    get_any_with_id(db, post_id)
+}
+
+pub fn delete(db: &DbConn, post_id: i32) -> QueryResult<usize> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl::*;
+
+    tags::remove_from_post(db, post_id)?;
+    categories::remove_from_post(db, post_id)?;
+    comments::remove_from_post(db, post_id)?;
+    diesel::delete(posts.find(post_id)).execute(&**db)
+}
+
 #[derive(Debug, Clone, FromForm)]
 pub struct NewPost {
-    pub title: Result<NonEmpty, &'static str>, // TODO add unique constraint, etc.
-    pub author: Result<NonEmpty, &'static str>,
+    pub title: Result<NonEmpty, &'static str>,
     pub body: Result<NonEmpty, &'static str>,
+    /// Shown on listing pages, generated from the body if left empty.
+    pub excerpt: String,
+    pub status: Result<PostStatus, &'static str>,
+    pub published_at: Result<OptionalDateTime, &'static str>,
+    /// Comma separated list of tag names.
+    pub tags: String,
+    /// Comma separated list of category names.
+    pub categories: String,
 }
 
 impl NewPost {
@@ -85,12 +450,23 @@ impl NewPost {
         if let Err(e) = self.title {
             m.insert("title".to_string(), format!("Title {}.", e));
         }
-        if let Err(e) = self.author {
-            m.insert("author".to_string(), format!("Author {}.", e));
-        }
         if let Err(e) = self.body {
             m.insert("body".to_string(), format!("Body {}.", e));
         }
+        if let Err(e) = self.status {
+            m.insert("status".to_string(), format!("Status {}.", e));
+        }
+        match self.published_at {
+            Err(e) => {
+                m.insert("published_at".to_string(), format!("Publish date {}.", e));
+            },
+            Ok(ref date) => {
+                let in_future = date.clone().into_inner().map_or(false, |date| date > Utc::now().naive_utc());
+                if self.status == Ok(PostStatus::Scheduled) && !in_future {
+                    m.insert("published_at".to_string(), "Publish date must be in the future for scheduled posts.".to_string());
+                }
+            },
+        }
 
         m
     }
@@ -102,22 +478,108 @@ pub struct NewDbPost {
     pub title: String,
     pub slug: String,
     pub author: String,
+    pub author_id: Option<i32>,
+    pub body: String,
+    pub body_html: String,
+    pub toc_html: String,
+    pub excerpt: String,
+    pub excerpt_html: String,
+    pub status: String,
+    pub published_at: Option<NaiveDateTime>,
+}
+
+impl NewDbPost {
+    /// Creates a new post from the validated changes, written by the given user.
+    pub fn new(changes: PostChanges, slug: String, author: &User) -> NewDbPost {
+        NewDbPost {
+            slug,
+            title: changes.title,
+            author: author.display_name.clone(),
+            author_id: Some(author.id),
+            body: changes.body,
+            body_html: changes.body_html,
+            toc_html: changes.toc_html,
+            excerpt: changes.excerpt,
+            excerpt_html: changes.excerpt_html,
+            status: changes.status,
+            published_at: changes.published_at,
+        }
+    }
+}
+
+/// The fields of a post that can be changed after it was created.
+/// The slug and author are intentionally left out, so existing links keep working.
+#[derive(Debug, Clone, Eq, PartialEq, AsChangeset)]
+#[table_name="posts"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct PostChanges {
+    pub title: String,
     pub body: String,
+    /// The rendered `body` and its table of contents, empty until [`render`] is called.
+    ///
+    /// [`render`]: #method.render
+    pub body_html: String,
+    pub toc_html: String,
+    pub excerpt: String,
+    /// The rendered excerpt, empty until [`render`] is called.
+    ///
+    /// [`render`]: #method.render
+    pub excerpt_html: String,
+    pub status: String,
+    pub published_at: Option<NaiveDateTime>,
+}
+
+impl PostChanges {
+    /// Renders the markdown of the body and excerpt including their shortcodes to `body_html`,
+    /// `toc_html` and `excerpt_html`.
+    /// Shortcodes that can't be rendered are reported as errors of the field they are used in.
+    pub fn render(mut self, db: &DbConn, renderer: &Renderer) -> Result<PostChanges, HashMap<String, String>> {
+        let mut errors = HashMap::new();
+
+        match renderer.render_with_shortcodes(db, &self.body) {
+            Ok(rendered) => {
+                self.body_html = rendered.html;
+                self.toc_html = rendered.toc;
+            },
+            Err(messages) => {
+                errors.insert("body".to_string(), messages.join(" "));
+            },
+        }
+        match renderer.render_excerpt(db, &self.excerpt, &self.body) {
+            Ok(html) => self.excerpt_html = html,
+            // errors in the body are already reported
+            Err(_) if errors.contains_key("body") && self.excerpt.trim().is_empty() => {},
+            Err(messages) => {
+                errors.insert("excerpt".to_string(), messages.join(" "));
+            },
+        }
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(errors)
+        }
+    }
 }
 
-impl<'a, 'r> From<&'a NewPost> for Result<NewDbPost, HashMap<String, String>> {
+impl<'a> From<&'a NewPost> for Result<PostChanges, HashMap<String, String>> {
     fn from(post: &'a NewPost) -> Self {
         let errors = post.errors();
         if errors.is_empty() {
             // this is all safe to unwrap, because we got no errors.
             let post = post.clone();
-            let title = post.title.unwrap().into_inner();
+            let status = post.status.unwrap();
+            let published_at = publish_date(status, post.published_at.unwrap().into_inner());
 
-            Ok(NewDbPost {
-                slug: slug(&title),
-                title,
-                author: post.author.unwrap().into_inner(),
+            Ok(PostChanges {
+                title: post.title.unwrap().into_inner(),
                 body: post.body.unwrap().into_inner(),
+                body_html: String::new(),
+                toc_html: String::new(),
+                excerpt: post.excerpt.trim().to_string(),
+                excerpt_html: String::new(),
+                status: status.as_str().to_string(),
+                published_at,
             })
         } else {
             Err(errors)
@@ -125,9 +587,31 @@ impl<'a, 'r> From<&'a NewPost> for Result<NewDbPost, HashMap<String, String>> {
     }
 }
 
-/// Creates a url-slug from the given title.
-/// Lowercases the title and replaces all whitespace with dashes ("-").
-pub fn slug(title: &str) -> String {
-    title.to_lowercase().replace(|c: char| c.is_whitespace(), "-")
-        .replace("#", "").replace("/", "")
-}
\ No newline at end of file
+/// Returns the `published_at` date to store for a post with the given status.
+/// Drafts don't have a publish date yet, published posts without an explicit date are
+/// published right now.
+fn publish_date(status: PostStatus, date: Option<NaiveDateTime>) -> Option<NaiveDateTime> {
+    match status {
+        PostStatus::Draft => None,
+        _ => date.or_else(|| Some(Utc::now().naive_utc())),
+    }
+}
+
+/// Creates a slug from the given title that is not used by any other post yet.
+/// Slugs consisting only of digits are prefixed, because `/post/<id>` would match them first.
+fn unique_slug(db: &DbConn, title: &str) -> QueryResult<String> {
+    let mut base = slug::slugify(title);
+    if base.chars().all(|c| c.is_digit(10)) {
+        base = if base.is_empty() { "post".to_string() } else { format!("post-{}", base) };
+    }
+
+    slug::unique(&base, |candidate| {
+        use diesel::prelude::*;
+        use db::schema::posts::dsl::*;
+
+        posts.filter(slug.eq(candidate))
+            .count()
+            .get_result::<i64>(&**db)
+            .map(|count| count > 0)
+    })
+}
@@ -0,0 +1,154 @@
+use std::cmp::Ordering;
+use diesel::prelude::*;
+use diesel::expression::sql_literal::sql;
+use diesel::types::{Integer, Text, Double};
+
+use controllers::{posts, pages};
+use db::DbConn;
+use routes::Urlify;
+
+/// Maximum number of results shown on the search page.
+const MAX_RESULTS: usize = 20;
+/// Number of matches loaded from each index, hidden posts are filtered out afterwards.
+const MAX_MATCHES: usize = 100;
+/// Maximum number of terms taken from a query, the rest is ignored.
+const MAX_TERMS: usize = 10;
+
+/// Markers `snippet()` puts around matches, replaced with `<mark>` once the snippet is escaped.
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+/// A post or page matching a search query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    /// Escaped html of the matching part of the body, with the matches wrapped in `<mark>`.
+    pub snippet: String,
+    /// bm25 score of the match, lower is better.
+    #[serde(skip_serializing)]
+    pub rank: f64,
+}
+
+/// Searches the titles and bodies of visible posts and all pages for the given terms.
+/// Returns the best matches first, or nothing if `terms` doesn't contain any words.
+pub fn search(db: &DbConn, terms: &str) -> QueryResult<Vec<SearchResult>> {
+    let query = match fts_query(terms) {
+        Some(query) => query,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut results = Vec::new();
+
+    let post_matches = matches(db, "__search_posts", &query)?;
+    let ids = post_matches.iter().map(|&(id, _, _)| id).collect();
+    for post in posts::visible_with_ids(db, ids)? {
+        if let Some(&(_, ref snippet, rank)) = post_matches.iter().find(|m| m.0 == post.id) {
+            results.push(SearchResult { url: post.url(), title: post.title, snippet: highlight(snippet), rank });
+        }
+    }
+
+    let page_matches = matches(db, "__search_pages", &query)?;
+    let ids = page_matches.iter().map(|&(id, _, _)| id).collect();
+    for page in pages::with_ids(db, ids)? {
+        if let Some(&(_, ref snippet, rank)) = page_matches.iter().find(|m| m.0 == page.id) {
+            results.push(SearchResult { url: page.url(), title: page.title, snippet: highlight(snippet), rank });
+        }
+    }
+
+    results.sort_by(|a, b| a.rank.partial_cmp(&b.rank).unwrap_or(Ordering::Equal));
+    results.truncate(MAX_RESULTS);
+    Ok(results)
+}
+
+/// Loads the row id, snippet and rank of the best matches in the given full-text index.
+/// `table` is never user input, `query` has to come from `fts_query`.
+fn matches(db: &DbConn, table: &str, query: &str) -> QueryResult<Vec<(i32, String, f64)>> {
+    let statement = format!(
+        "SELECT rowid, snippet({table}, -1, char(2), char(3), '…', 24), rank \
+         FROM {table} WHERE {table} MATCH '{query}' ORDER BY rank LIMIT {limit}",
+        table = table,
+        query = query,
+        limit = MAX_MATCHES,
+    );
+
+    sql::<(Integer, Text, Double)>(&statement).load::<(i32, String, f64)>(&**db)
+}
+
+/// Turns user input into an FTS5 query matching all words as prefixes.
+/// Everything but letters and digits is dropped, so the result is safe to put into a string
+/// literal and can't use the FTS5 query syntax.
+pub fn fts_query(terms: &str) -> Option<String> {
+    let words: Vec<String> = terms.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .take(MAX_TERMS)
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
+/// Escapes a snippet and wraps the matches marked by `snippet()` in `<mark>`.
+fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+
+    for c in snippet.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#x27;"),
+            c => html.push(c),
+        }
+    }
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_match_word_prefixes() {
+        assert_eq!(fts_query("rust"), Some("\"rust\"*".to_string()));
+        assert_eq!(fts_query("  rocket   diesel "), Some("\"rocket\"* \"diesel\"*".to_string()));
+        assert_eq!(fts_query("Übergrößen café"), Some("\"Übergrößen\"* \"café\"*".to_string()));
+    }
+
+    #[test]
+    fn queries_drop_syntax() {
+        assert_eq!(fts_query("title:rust OR NEAR(a b)"), Some("\"title\"* \"rust\"* \"OR\"* \"NEAR\"* \"a\"* \"b\"*".to_string()));
+        assert_eq!(fts_query("it's \"quoted\"*"), Some("\"it\"* \"s\"* \"quoted\"*".to_string()));
+        assert_eq!(fts_query("'; DROP TABLE posts; --"), Some("\"DROP\"* \"TABLE\"* \"posts\"*".to_string()));
+    }
+
+    #[test]
+    fn empty_queries() {
+        assert_eq!(fts_query(""), None);
+        assert_eq!(fts_query("   "), None);
+        assert_eq!(fts_query("*** ?!"), None);
+    }
+
+    #[test]
+    fn queries_are_limited() {
+        let terms = (0..20).map(|i| format!("w{}", i)).collect::<Vec<_>>().join(" ");
+        assert_eq!(fts_query(&terms).unwrap().split(' ').count(), MAX_TERMS);
+    }
+
+    #[test]
+    fn snippets_are_escaped() {
+        assert_eq!(highlight("a \u{2}match\u{3} here"), "a <mark>match</mark> here");
+        assert_eq!(
+            highlight("<script>alert('\u{2}x\u{3}')</script> & \"more\""),
+            "&lt;script&gt;alert(&#x27;<mark>x</mark>&#x27;)&lt;/script&gt; &amp; &quot;more&quot;"
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use diesel::prelude::*;
+
+use controllers::posts;
+use db::models::Tag;
+use db::schema::{tags, post_tags};
+use db::DbConn;
+
+terms! {
+    model: Tag,
+    table: tags,
+    join_table: post_tags,
+    column: tag_id,
+    new: NewTag,
+    new_join: NewPostTag,
+}
+
+/// Loads all tags of posts that are visible to the public together with the number of those
+/// posts using them, ordered by name.
+pub fn with_counts(db: &DbConn) -> QueryResult<Vec<(Tag, i64)>> {
+    use diesel::prelude::*;
+    use db::schema::posts::dsl as post;
+
+    let visible = post::posts.filter(posts::is_visible()).select(post::id);
+    let used = post_tags::table
+        .filter(post_tags::post_id.eq_any(visible))
+        .select(post_tags::tag_id)
+        .load::<i32>(&**db)?;
+
+    let mut counts: HashMap<i32, i64> = HashMap::new();
+    for tag in used {
+        *counts.entry(tag).or_insert(0) += 1;
+    }
+
+    let used_tags = tags::table
+        .filter(tags::id.eq_any(counts.keys().cloned().collect::<Vec<_>>()))
+        .order(tags::name.asc())
+        .load::<Tag>(&**db)?;
+
+    Ok(used_tags.into_iter()
+        .map(|tag| {
+            let count = counts.get(&tag.id).cloned().unwrap_or(0);
+            (tag, count)
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Insertable)]
+#[table_name="tags"]
+struct NewTag {
+    name: String,
+    slug: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Insertable)]
+#[table_name="post_tags"]
+struct NewPostTag {
+    post_id: i32,
+    tag_id: i32,
+}
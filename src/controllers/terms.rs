@@ -0,0 +1,103 @@
+/// Implements the functions shared by tags and categories. Both are terms with a `name` and a
+/// `slug` that are attached to posts through a join table with a `post_id` and a `$column`.
+///
+/// The calling module has to define the insertable structs `$new` for the terms and `$new_join`
+/// for the join table, which can't be derived inside a macro.
+///
+/// Posts are matched with subqueries instead of loading their ids first, so the queries don't
+/// grow with the number of posts.
+macro_rules! terms {
+    (
+        model: $model:ident,
+        table: $table:ident,
+        join_table: $join_table:ident,
+        column: $column:ident,
+        new: $new:ident,
+        new_join: $new_join:ident,
+    ) => {
+        pub fn get_with_slug(db: &::db::DbConn, term_slug: &str) -> ::diesel::QueryResult<$model> {
+            use diesel::prelude::*;
+            use db::schema::$table::dsl::*;
+
+            $table.filter(slug.eq(term_slug)).first(&**db)
+        }
+
+        /// Loads all terms of the given post ordered by name.
+        pub fn for_post(db: &::db::DbConn, post: i32) -> ::diesel::QueryResult<Vec<$model>> {
+            use diesel::prelude::*;
+            use db::schema::$table::dsl::*;
+            use db::schema::$join_table;
+
+            let term_ids = $join_table::table
+                .filter($join_table::post_id.eq(post))
+                .select($join_table::$column);
+
+            $table.filter(id.eq_any(term_ids)).order(name.asc()).load::<$model>(&**db)
+        }
+
+        /// Restricts a query for posts to the ones with the given term.
+        pub fn filter_posts<'a>(query: ::db::schema::posts::BoxedQuery<'a, ::diesel::sqlite::Sqlite>, term: i32) -> ::db::schema::posts::BoxedQuery<'a, ::diesel::sqlite::Sqlite> {
+            use diesel::prelude::*;
+            use db::schema::{posts, $join_table};
+
+            let post_ids = $join_table::table
+                .filter($join_table::$column.eq(term))
+                .select($join_table::post_id);
+
+            query.filter(posts::id.eq_any(post_ids))
+        }
+
+        /// Replaces the terms of the given post. Terms that don't exist yet are created.
+        pub fn set_for_post(db: &::db::DbConn, post: i32, names: &[String]) -> ::diesel::QueryResult<()> {
+            use diesel::prelude::*;
+
+            let mut ids = Vec::new();
+            for term_name in names {
+                if ::slug::slugify(term_name).is_empty() {
+                    continue;
+                }
+
+                let term = find_or_create(db, term_name)?;
+                if !ids.contains(&term.id) {
+                    ids.push(term.id);
+                }
+            }
+
+            remove_from_post(db, post)?;
+
+            let rows: Vec<$new_join> = ids.into_iter()
+                .map(|term| $new_join { post_id: post, $column: term })
+                .collect();
+            if rows.is_empty() {
+                return Ok(());
+            }
+
+            ::diesel::insert(&rows).into(::db::schema::$join_table::table).execute(&**db)?;
+
+            Ok(())
+        }
+
+        /// Removes all terms from the given post.
+        pub fn remove_from_post(db: &::db::DbConn, post: i32) -> ::diesel::QueryResult<usize> {
+            use diesel::prelude::*;
+            use db::schema::$join_table::dsl::*;
+
+            ::diesel::delete($join_table.filter(post_id.eq(post))).execute(&**db)
+        }
+
+        fn find_or_create(db: &::db::DbConn, term_name: &str) -> ::diesel::QueryResult<$model> {
+            use diesel::prelude::*;
+
+            let term_slug = ::slug::slugify(term_name);
+
+            match get_with_slug(db, &term_slug) {
+                Err(::diesel::result::Error::NotFound) => {
+                    let term = $new { name: term_name.to_string(), slug: term_slug.clone() };
+                    ::diesel::insert(&term).into(::db::schema::$table::table).execute(&**db)?;
+                    get_with_slug(db, &term_slug)
+                },
+                result => result,
+            }
+        }
+    };
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use bcrypt;
+use diesel;
+use diesel::prelude::*;
+
+use auth::permissions::Role;
+use db::models::User;
+use db::schema::users;
+use db::DbConn;
+use forms::NonEmpty;
+
+pub fn all(db: &DbConn) -> QueryResult<Vec<User>> {
+    use diesel::prelude::*;
+    use db::schema::users::dsl::*;
+
+    users.order(username.asc()).load::<User>(&**db)
+}
+
+pub fn get_with_id(db: &DbConn, user_id: i32) -> QueryResult<User> {
+    use diesel::prelude::*;
+    use db::schema::users::dsl::*;
+
+    users.find(user_id).first(&**db)
+}
+
+pub fn get_with_username(db: &DbConn, name: &str) -> QueryResult<User> {
+    use diesel::prelude::*;
+    use db::schema::users::dsl::*;
+
+    users.filter(username.eq(name)).first(&**db)
+}
+
+/// Checks the credentials of the given login and returns the matching user.
+pub fn try_login(db: &DbConn, login: &Login) -> Result<User, HashMap<String, String>> {
+    let errors = login.errors();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // this is all safe to unwrap, because we got no errors.
+    let login = login.clone();
+    let username = login.username.unwrap().into_inner();
+    let password = login.password.unwrap().into_inner();
+
+    get_with_username(db, &username)
+        .ok()
+        .and_then(|user| match bcrypt::verify(&password, &user.password_hash) {
+            Ok(true) => Some(user),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            convert_args!(hashmap!(
+                "general" => "Invalid username or password.",
+            ))
+        })
+}
+
+/// Creates a new user with a hashed version of the given password.
+pub fn create(db: &DbConn, username: &str, display_name: &str, email: &str, password: &str, role: Role) -> Result<User, String> {
+    let password_hash = bcrypt::hash(password, bcrypt::DEFAULT_COST)
+        .map_err(|err| format!("{:?}", err))?;
+
+    let user = NewDbUser {
+        username: username.to_string(),
+        display_name: display_name.to_string(),
+        email: email.to_string(),
+        password_hash,
+        role: role.as_str().to_string(),
+    };
+
+    diesel::insert(&user).into(users::table)
+        .execute(&**db) // TODO use get_result with non sqlite database
+        .map_err(|err| format!("{:?}", err))?;
+
+    get_with_username(db, username).map_err(|err| format!("{:?}", err))
+}
+
+pub fn try_create(db: &DbConn, user: &NewUser) -> Result<User, HashMap<String, String>> {
+    let errors = user.errors();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // this is all safe to unwrap, because we got no errors.
+    let user = user.clone();
+    let username = user.username.unwrap().into_inner();
+
+    if get_with_username(db, &username).is_ok() {
+        return Err(convert_args!(hashmap!(
+            "username" => "Username is already taken.",
+        )));
+    }
+
+    create(
+        db,
+        &username,
+        &user.display_name.unwrap().into_inner(),
+        &user.email.unwrap().into_inner(),
+        &user.password.unwrap().into_inner(),
+        user.role.unwrap(),
+    ).map_err(|err| {
+        // TODO add real logging here
+        println!("Error creating user: {:?}", err);
+
+        convert_args!(hashmap!(
+            "general" => "Error saving the user. Please try again later.",
+        ))
+    })
+}
+
+pub fn update_role(db: &DbConn, user_id: i32, new_role: Role) -> QueryResult<User> {
+    use diesel::prelude::*;
+    use db::schema::users::dsl::*;
+
+    diesel::update(users.find(user_id))
+        .set(role.eq(new_role.as_str()))
+        .execute(&**db)?;
+    get_with_id(db, user_id)
+}
+
+#[derive(Debug, Clone, FromForm)]
+pub struct Login {
+    pub username: Result<NonEmpty, &'static str>,
+    pub password: Result<NonEmpty, &'static str>,
+}
+
+impl Login {
+    pub fn errors(&self) -> HashMap<String, String> {
+        let mut m = HashMap::new();
+
+        if let Err(e) = self.username {
+            m.insert("username".to_string(), format!("Username {}.", e));
+        }
+        if let Err(e) = self.password {
+            m.insert("password".to_string(), format!("Password {}.", e));
+        }
+
+        m
+    }
+}
+
+#[derive(Debug, Clone, FromForm)]
+pub struct NewUser {
+    pub username: Result<NonEmpty, &'static str>,
+    pub display_name: Result<NonEmpty, &'static str>,
+    pub email: Result<NonEmpty, &'static str>,
+    pub password: Result<NonEmpty, &'static str>,
+    pub role: Result<Role, &'static str>,
+}
+
+impl NewUser {
+    pub fn errors(&self) -> HashMap<String, String> {
+        let mut m = HashMap::new();
+
+        if let Err(e) = self.username {
+            m.insert("username".to_string(), format!("Username {}.", e));
+        }
+        if let Err(e) = self.display_name {
+            m.insert("display_name".to_string(), format!("Display name {}.", e));
+        }
+        if let Err(e) = self.email {
+            m.insert("email".to_string(), format!("Email {}.", e));
+        }
+        if let Err(e) = self.password {
+            m.insert("password".to_string(), format!("Password {}.", e));
+        }
+        if let Err(e) = self.role {
+            m.insert("role".to_string(), format!("Role {}.", e));
+        }
+
+        m
+    }
+}
+
+/// Form to change the role of an existing user.
+#[derive(Debug, Clone, FromForm)]
+pub struct ChangeRole {
+    pub role: Result<Role, &'static str>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Insertable)]
+#[table_name="users"]
+pub struct NewDbUser {
+    pub username: String,
+    pub display_name: String,
+    pub email: String,
+    pub password_hash: String,
+    pub role: String,
+}
@@ -12,7 +12,7 @@ use r2d2;
 
 // Managed State
 
-type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+pub type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
 
 // TODO maybe use lazy_static
 static DATABASE_URL: &'static str = "satellite.sqlite";//env!("DATABASE_URL");
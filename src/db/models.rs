@@ -1,5 +1,7 @@
-use chrono::NaiveDateTime;
+use std::str::FromStr;
+use chrono::{NaiveDateTime, Utc};
 use routes::Urlify;
+use auth::permissions::Role;
 
 #[derive(Debug, Clone, Eq, PartialEq, Queryable, Serialize)]
 pub struct Post {
@@ -9,10 +11,35 @@ pub struct Post {
     pub body: String,
     pub author: String,
     pub published_at: Option<NaiveDateTime>,
+    pub status: String,
+    pub author_id: Option<i32>,
+    pub views: i32,
+    /// The `body` rendered to sanitized html when the post was saved.
+    pub body_html: String,
+    /// Table of contents of the rendered `body`, may be empty.
+    pub toc_html: String,
+    /// Markdown shown on listing pages instead of the body, may be empty.
+    pub excerpt: String,
+    /// The rendered `excerpt`, or the start of the body if there is no excerpt.
+    pub excerpt_html: String,
 }
 
 impl Post {
     const BASE_URL: &'static str = "/post";
+
+    /// Returns the current status of this post.
+    /// A scheduled post whose `published_at` has passed is considered published, a published
+    /// post with a date in the future is hidden until then and considered scheduled.
+    pub fn status(&self) -> PostStatus {
+        let status = self.status.parse().unwrap_or(PostStatus::Draft);
+        let now = Utc::now().naive_utc();
+
+        match (status, self.published_at) {
+            (PostStatus::Scheduled, Some(date)) if date <= now => PostStatus::Published,
+            (PostStatus::Published, Some(date)) if date > now => PostStatus::Scheduled,
+            (status, _) => status,
+        }
+    }
 }
 
 impl Urlify for Post {
@@ -23,4 +50,272 @@ impl Urlify for Post {
     fn short_url(&self) -> String {
         format!("{}/{}", Self::BASE_URL, self.id)
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Queryable, Serialize)]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+}
+
+impl Tag {
+    const BASE_URL: &'static str = "/tag";
+}
+
+impl Urlify for Tag {
+    fn url(&self) -> String {
+        format!("{}/{}", Self::BASE_URL, self.slug)
+    }
+
+    fn short_url(&self) -> String {
+        self.url()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Queryable, Serialize)]
+pub struct Category {
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+}
+
+impl Category {
+    const BASE_URL: &'static str = "/category";
+}
+
+impl Urlify for Category {
+    fn url(&self) -> String {
+        format!("{}/{}", Self::BASE_URL, self.slug)
+    }
+
+    fn short_url(&self) -> String {
+        self.url()
+    }
+}
+
+/// A reader comment on a [`Post`], or a reply to another comment if `parent_id` is set.
+/// Comments are only shown once they are approved.
+///
+/// [`Post`]: struct.Post.html
+#[derive(Debug, Clone, Eq, PartialEq, Queryable, Serialize)]
+pub struct Comment {
+    pub id: i32,
+    pub post_id: i32,
+    pub author_name: String,
+    pub author_email: String,
+    pub body: String,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub parent_id: Option<i32>,
+}
+
+/// A static page like "About", served at `/<path>`.
+/// Pages can be nested, the `path` is made up of the slugs of all ancestors and the page itself,
+/// e.g. `docs/install/linux`.
+#[derive(Debug, Clone, Eq, PartialEq, Queryable, Serialize)]
+pub struct Page {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub body: String,
+    pub updated_at: NaiveDateTime,
+    pub parent_id: Option<i32>,
+    pub path: String,
+}
+
+impl Urlify for Page {
+    fn url(&self) -> String {
+        format!("/{}", self.path)
+    }
+
+    fn short_url(&self) -> String {
+        self.url()
+    }
+}
+
+/// A file in the media library, served at `/media/<filename>`.
+#[derive(Debug, Clone, Eq, PartialEq, Queryable, Serialize)]
+pub struct Media {
+    pub id: i32,
+    /// Name of the file in the uploads directory.
+    pub filename: String,
+    /// Name of the file on the computer of the uploader.
+    pub original_name: String,
+    /// Detected from the content of the file when it was uploaded.
+    pub mime_type: String,
+    pub size: i32,
+    pub uploader_id: Option<i32>,
+    pub uploaded_at: NaiveDateTime,
+    /// Size of the image, not set if it couldn't be decoded.
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+impl Media {
+    pub const BASE_URL: &'static str = "/media";
+
+    /// Returns the markdown that embeds this file as an image, using the original name without
+    /// its extension as alt text.
+    pub fn markdown(&self) -> String {
+        let alt = match self.original_name.rfind('.') {
+            Some(dot) if dot > 0 => &self.original_name[..dot],
+            _ => self.original_name.as_str(),
+        };
+        let alt: String = alt.chars().filter(|&c| c != '[' && c != ']' && c != '\\').collect();
+
+        format!("![{}]({})", alt, self.url())
+    }
+}
+
+impl Urlify for Media {
+    fn url(&self) -> String {
+        format!("{}/{}", Self::BASE_URL, self.filename)
+    }
+
+    fn short_url(&self) -> String {
+        self.url()
+    }
+}
+
+/// A scaled down copy of an image in the media library, stored next to the original.
+#[derive(Debug, Clone, Eq, PartialEq, Queryable, Serialize)]
+pub struct MediaVariant {
+    pub id: i32,
+    pub media_id: i32,
+    /// One of the sizes configured in the `[uploads.variants]` section, e.g. `thumbnail`.
+    pub name: String,
+    pub filename: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Urlify for MediaVariant {
+    fn url(&self) -> String {
+        format!("{}/{}", Media::BASE_URL, self.filename)
+    }
+
+    fn short_url(&self) -> String {
+        self.url()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Queryable, Serialize)]
+pub struct User {
+    pub id: i32,
+    pub username: String,
+    pub display_name: String,
+    pub email: String,
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    pub created_at: NaiveDateTime,
+    pub role: String,
+}
+
+impl User {
+    /// Returns the role of this user.
+    /// Unknown roles are treated like the least privileged one.
+    pub fn role(&self) -> Role {
+        self.role.parse().unwrap_or(Role::Author)
+    }
+
+    /// Returns `true` if this user may edit the given post.
+    /// Admins and editors may edit every post, authors only their own.
+    pub fn can_edit(&self, post: &Post) -> bool {
+        match self.role() {
+            Role::Admin | Role::Editor => true,
+            Role::Author => post.author_id == Some(self.id),
+        }
+    }
+
+    /// Returns `true` if this user may delete the given file from the media library.
+    /// Admins and editors may delete every file, authors only their own uploads.
+    pub fn can_delete(&self, media: &Media) -> bool {
+        match self.role() {
+            Role::Admin | Role::Editor => true,
+            Role::Author => media.uploader_id == Some(self.id),
+        }
+    }
+}
+
+/// The lifecycle state of a [`Post`].
+/// Stored as a lowercase string in the `posts.status` column.
+///
+/// [`Post`]: struct.Post.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostStatus {
+    /// Work in progress, only visible in the backend.
+    Draft,
+    /// Becomes visible once `published_at` has passed.
+    Scheduled,
+    Published,
+    /// No longer listed, but kept in the database.
+    Archived,
+}
+
+impl PostStatus {
+    /// All statuses that make a post publicly visible (once `published_at` has passed).
+    pub const PUBLIC: [PostStatus; 2] = [PostStatus::Scheduled, PostStatus::Published];
+
+    /// Returns the name of this status as stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            PostStatus::Draft => "draft",
+            PostStatus::Scheduled => "scheduled",
+            PostStatus::Published => "published",
+            PostStatus::Archived => "archived",
+        }
+    }
+}
+
+impl FromStr for PostStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(PostStatus::Draft),
+            "scheduled" => Ok(PostStatus::Scheduled),
+            "published" => Ok(PostStatus::Published),
+            "archived" => Ok(PostStatus::Archived),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The moderation state of a [`Comment`].
+/// Stored as a lowercase string in the `comments.status` column.
+///
+/// [`Comment`]: struct.Comment.html
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentStatus {
+    /// Waiting in the moderation queue.
+    Pending,
+    Approved,
+    Spam,
+}
+
+impl CommentStatus {
+    /// Returns the name of this status as stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            CommentStatus::Pending => "pending",
+            CommentStatus::Approved => "approved",
+            CommentStatus::Spam => "spam",
+        }
+    }
+}
+
+impl FromStr for CommentStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(CommentStatus::Pending),
+            "approved" => Ok(CommentStatus::Approved),
+            "spam" => Ok(CommentStatus::Spam),
+            _ => Err(()),
+        }
+    }
+}
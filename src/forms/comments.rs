@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use controllers::comments::NewComment;
+use db::models::CommentStatus;
+use super::NonEmpty;
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize)]
+pub struct CommentForm {
+    errors: HashMap<String, String>,
+    author_name: String,
+    author_email: String,
+    body: String,
+    parent: Option<i32>,
+}
+
+impl CommentForm {
+    pub fn with_errors(comment: NewComment, errors: HashMap<String, String>) -> CommentForm {
+        CommentForm {
+            errors,
+            author_name: comment.author_name.map(NonEmpty::into_inner).unwrap_or_default(),
+            author_email: comment.author_email.map(NonEmpty::into_inner).unwrap_or_default(),
+            body: comment.body.map(NonEmpty::into_inner).unwrap_or_default(),
+            parent: comment.parent,
+        }
+    }
+}
+
+/// Query string used to select the comments shown in the moderation queue.
+/// Shows pending comments if the status is missing or unknown.
+#[derive(Debug, Clone, Eq, PartialEq, Default, FromForm, Serialize)]
+pub struct CommentFilter {
+    status: Option<String>,
+}
+
+impl CommentFilter {
+    pub fn status(&self) -> CommentStatus {
+        self.status.as_ref()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(CommentStatus::Pending)
+    }
+}
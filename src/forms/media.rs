@@ -0,0 +1,98 @@
+use std::io::Read;
+
+use multipart::server::Multipart;
+use rocket::{Data, Outcome, Request, State};
+use rocket::data::{self, FromData};
+use rocket::http::Status;
+
+use config::Config;
+use context_builder::csrf::{CsrfToken, CSRF_FIELD};
+
+/// Name of the file field of the upload form.
+const FILE_FIELD: &'static str = "file";
+
+/// Space for the CSRF token, the multipart headers and the read-ahead of the parser on top of the
+/// maximum file size. Reading stops after the file, so only malformed forms can exceed this.
+const OVERHEAD: u64 = 1024 * 1024;
+
+/// A file uploaded with a `multipart/form-data` form, the multipart counterpart of [`CsrfForm`].
+/// Fails with `BadRequest` if the form is not multipart or the CSRF token of the visitor doesn't
+/// come before the file, so nothing is read from forms sent by other sites.
+///
+/// At most `max_size + 1` bytes of the file are read and everything after it is ignored. Larger
+/// files are cut off there and fail the size check of [`media::try_insert`] like any other
+/// invalid upload.
+///
+/// [`CsrfForm`]: ../../../context_builder/csrf/struct.CsrfForm.html
+/// [`media::try_insert`]: ../../controllers/media/fn.try_insert.html
+#[derive(Debug)]
+pub struct MediaUpload {
+    /// Name of the file on the computer of the uploader, empty if no file was selected.
+    pub filename: String,
+    pub data: Vec<u8>,
+}
+
+impl FromData for MediaUpload {
+    type Error = Option<String>;
+
+    fn from_data(request: &Request, data: Data) -> data::Outcome<Self, Self::Error> {
+        let boundary = match request.content_type() {
+            Some(ct) if ct.is_form_data() => ct.params()
+                .find(|&(key, _)| key == "boundary")
+                .map(|(_, value)| value.to_string()),
+            _ => None,
+        };
+        let boundary = match boundary {
+            Some(boundary) => boundary,
+            None => return Outcome::Failure((Status::BadRequest, Some("not a multipart form".to_string()))),
+        };
+
+        let max_size = match request.guard::<State<Config>>() {
+            Outcome::Success(config) => config.uploads.max_size,
+            _ => return Outcome::Failure((Status::InternalServerError, None)),
+        };
+        let token = match request.guard::<CsrfToken>() {
+            Outcome::Success(token) => token,
+            _ => return Outcome::Failure((Status::BadRequest, Some("invalid csrf token".to_string()))),
+        };
+
+        let mut multipart = Multipart::with_body(data.open().take(max_size + OVERHEAD), boundary);
+        let mut verified = false;
+
+        loop {
+            let mut field = match multipart.read_entry() {
+                Ok(Some(field)) => field,
+                Ok(None) => break,
+                Err(e) => return Outcome::Failure((Status::BadRequest, Some(format!("{:?}", e)))),
+            };
+
+            if &*field.headers.name == CSRF_FIELD {
+                let mut submitted = String::new();
+                if let Err(e) = field.data.by_ref().take(1024).read_to_string(&mut submitted) {
+                    return Outcome::Failure((Status::BadRequest, Some(format!("{:?}", e))));
+                }
+                verified = token.verify(&submitted);
+            } else if &*field.headers.name == FILE_FIELD {
+                if !verified {
+                    return Outcome::Failure((Status::BadRequest, Some("invalid csrf token".to_string())));
+                }
+
+                let mut upload = MediaUpload {
+                    filename: field.headers.filename.clone().unwrap_or_default(),
+                    data: Vec::new(),
+                };
+                if let Err(e) = field.data.by_ref().take(max_size + 1).read_to_end(&mut upload.data) {
+                    return Outcome::Failure((Status::BadRequest, Some(format!("{:?}", e))));
+                }
+                return Outcome::Success(upload);
+            }
+        }
+
+        if !verified {
+            return Outcome::Failure((Status::BadRequest, Some("invalid csrf token".to_string())));
+        }
+
+        // no file was selected
+        Outcome::Success(MediaUpload { filename: String::new(), data: Vec::new() })
+    }
+}
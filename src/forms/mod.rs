@@ -1,8 +1,17 @@
 pub mod posts;
+pub mod pages;
+pub mod users;
+pub mod comments;
+pub mod media;
+pub mod search;
 
+use chrono::NaiveDateTime;
 use rocket::http::RawStr;
 use rocket::request::FromFormValue;
 
+use auth::permissions::Role;
+use db::models::PostStatus;
+
 #[derive(Debug, Clone)]
 pub struct NonEmpty(String);
 
@@ -27,3 +36,54 @@ impl<'v> FromFormValue<'v> for NonEmpty {
     }
 }
 
+/// Splits a comma separated list into its trimmed, non-empty entries.
+pub fn parse_list(input: &str) -> Vec<String> {
+    input.split(',')
+        .map(|entry| entry.trim())
+        .filter(|entry| !entry.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// An optional date and time as sent by `<input type="datetime-local">`.
+/// An empty value is accepted and results in `None`.
+#[derive(Debug, Clone)]
+pub struct OptionalDateTime(Option<NaiveDateTime>);
+
+impl OptionalDateTime {
+    pub fn into_inner(self) -> Option<NaiveDateTime> {
+        self.0
+    }
+}
+
+impl<'v> FromFormValue<'v> for OptionalDateTime {
+    type Error = &'static str;
+
+    fn from_form_value(form_value: &'v RawStr) -> Result<Self, Self::Error> {
+        if form_value.is_empty() {
+            return Ok(OptionalDateTime(None));
+        }
+
+        let value = form_value.url_decode().map_err(|_| "could not be decoded as utf-8")?;
+        NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M")
+            .or_else(|_| NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M:%S"))
+            .map(|date| OptionalDateTime(Some(date)))
+            .map_err(|_| "is not a valid date")
+    }
+}
+
+impl<'v> FromFormValue<'v> for PostStatus {
+    type Error = &'static str;
+
+    fn from_form_value(form_value: &'v RawStr) -> Result<Self, Self::Error> {
+        form_value.as_str().parse().map_err(|_| "is not a valid status")
+    }
+}
+
+impl<'v> FromFormValue<'v> for Role {
+    type Error = &'static str;
+
+    fn from_form_value(form_value: &'v RawStr) -> Result<Self, Self::Error> {
+        form_value.as_str().parse().map_err(|_| "is not a valid role")
+    }
+}
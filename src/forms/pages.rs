@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use controllers::pages::NewPage;
+use db::models::Page;
+use super::NonEmpty;
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize)]
+pub struct NewPageForm {
+    id: Option<i32>,
+    errors: HashMap<String, String>,
+    title: String,
+    slug: String,
+    body: String,
+    parent: Option<i32>,
+    /// The pages that can be selected as parent.
+    parents: Vec<Page>,
+}
+
+impl NewPageForm {
+    pub fn with_errors(page: NewPage, errors: HashMap<String, String>) -> NewPageForm {
+        NewPageForm {
+            id: None,
+            errors,
+            title: page.title.map(NonEmpty::into_inner).unwrap_or_default(),
+            slug: page.slug,
+            body: page.body.map(NonEmpty::into_inner).unwrap_or_default(),
+            parent: page.parent,
+            parents: Vec::new(),
+        }
+    }
+
+    /// Sets the id of the page this form edits.
+    pub fn for_page(mut self, id: i32) -> NewPageForm {
+        self.id = Some(id);
+        self
+    }
+
+    /// Sets the pages that can be selected as parent.
+    pub fn with_parents(mut self, parents: Vec<Page>) -> NewPageForm {
+        self.parents = parents;
+        self
+    }
+
+    /// Creates a form pre-filled with the values of an existing page.
+    pub fn from_page(page: &Page) -> NewPageForm {
+        NewPageForm {
+            id: Some(page.id),
+            errors: HashMap::new(),
+            title: page.title.clone(),
+            slug: page.slug.clone(),
+            body: page.body.clone(),
+            parent: page.parent_id,
+            parents: Vec::new(),
+        }
+    }
+}
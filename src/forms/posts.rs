@@ -1,13 +1,19 @@
 use std::collections::HashMap;
 use controllers::posts::NewPost;
+use db::models::{Post, PostStatus, Tag, Category};
 use super::NonEmpty;
 
 #[derive(Debug, Clone, Eq, PartialEq, Default, Serialize)]
 pub struct NewPostForm {
+    id: Option<i32>,
     errors: HashMap<String, String>,
     title: String,
-    author: String,
     body: String,
+    excerpt: String,
+    status: String,
+    published_at: String,
+    tags: String,
+    categories: String,
 }
 
 impl NewPostForm {
@@ -20,10 +26,64 @@ impl NewPostForm {
         }
 
         NewPostForm {
+            id: None,
             errors,
             title: unwrap(post.title),
-            author: unwrap(post.author),
             body: unwrap(post.body),
+            excerpt: post.excerpt,
+            status: post.status.map(|s| s.as_str().to_string()).unwrap_or_default(),
+            published_at: post.published_at.ok()
+                .and_then(|date| date.into_inner())
+                .map(|date| date.format("%Y-%m-%dT%H:%M").to_string())
+                .unwrap_or_default(),
+            tags: post.tags,
+            categories: post.categories,
         }
     }
+
+    /// Sets the id of the post this form edits.
+    pub fn for_post(mut self, id: i32) -> NewPostForm {
+        self.id = Some(id);
+        self
+    }
+
+    /// Creates a form pre-filled with the values of an existing post.
+    pub fn from_post(post: &Post, tags: &[Tag], categories: &[Category]) -> NewPostForm {
+        NewPostForm {
+            id: Some(post.id),
+            errors: HashMap::new(),
+            title: post.title.clone(),
+            body: post.body.clone(),
+            excerpt: post.excerpt.clone(),
+            // a scheduled post whose date has passed is live and is saved as published
+            status: post.status().as_str().to_string(),
+            published_at: post.published_at
+                .map(|date| date.format("%Y-%m-%dT%H:%M").to_string())
+                .unwrap_or_default(),
+            tags: join_names(tags.iter().map(|tag| tag.name.as_str())),
+            categories: join_names(categories.iter().map(|category| category.name.as_str())),
+        }
+    }
+}
+
+fn join_names<'a, I: Iterator<Item=&'a str>>(names: I) -> String {
+    names.collect::<Vec<_>>().join(", ")
+}
+
+/// Query string used to filter the post list in the backend.
+/// Empty values are treated like missing ones.
+#[derive(Debug, Clone, Eq, PartialEq, Default, FromForm, Serialize)]
+pub struct PostFilter {
+    status: Option<String>,
+    author: Option<String>,
+}
+
+impl PostFilter {
+    pub fn status(&self) -> Option<PostStatus> {
+        self.status.as_ref().and_then(|s| s.parse().ok())
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_ref().and_then(|s| if s.is_empty() { None } else { Some(s.as_str()) })
+    }
 }
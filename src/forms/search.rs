@@ -0,0 +1,12 @@
+/// Query string of the search page.
+#[derive(Debug, Clone, Eq, PartialEq, Default, FromForm, Serialize)]
+pub struct SearchQuery {
+    q: Option<String>,
+}
+
+impl SearchQuery {
+    /// Returns the trimmed search terms, empty if nothing was searched for.
+    pub fn terms(&self) -> &str {
+        self.q.as_ref().map(|q| q.trim()).unwrap_or("")
+    }
+}
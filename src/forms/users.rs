@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+use controllers::users::{Login, NewUser};
+use super::NonEmpty;
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize)]
+pub struct LoginForm {
+    errors: HashMap<String, String>,
+    username: String,
+}
+
+impl LoginForm {
+    pub fn with_errors(login: Login, errors: HashMap<String, String>) -> LoginForm {
+        LoginForm {
+            errors,
+            username: login.username.map(NonEmpty::into_inner).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize)]
+pub struct NewUserForm {
+    errors: HashMap<String, String>,
+    username: String,
+    display_name: String,
+    email: String,
+    role: String,
+}
+
+impl NewUserForm {
+    pub fn with_errors(user: NewUser, errors: HashMap<String, String>) -> NewUserForm {
+        NewUserForm {
+            errors,
+            username: user.username.map(NonEmpty::into_inner).unwrap_or_default(),
+            display_name: user.display_name.map(NonEmpty::into_inner).unwrap_or_default(),
+            email: user.email.map(NonEmpty::into_inner).unwrap_or_default(),
+            role: user.role.map(|r| r.as_str().to_string()).unwrap_or_default(),
+        }
+    }
+}
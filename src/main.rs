@@ -22,6 +22,16 @@ extern crate chrono;
 extern crate pulldown_cmark;
 #[macro_use]
 extern crate maplit;
+extern crate bcrypt;
+extern crate unidecode;
+extern crate toml;
+extern crate ring;
+extern crate ammonia;
+extern crate syntect;
+extern crate tera;
+extern crate multipart;
+extern crate image;
+extern crate rpassword;
 
 extern crate context_builder;
 
@@ -37,18 +47,72 @@ mod forms;
 mod response;
 mod request;
 
+/// Contains the `User` request guard and session helpers.
+mod auth;
+
+/// Contains helpers for creating url slugs.
+mod slug;
+
+/// Contains the providers for dynamic sidebar items.
+mod sidebar;
+
+/// Contains the settings read from `Satellite.toml`.
+mod config;
+
+/// Contains the markdown renderer and html sanitizer.
+mod markdown;
+
+/// Contains the shortcodes that can be used in posts.
+mod shortcodes;
+
+/// Contains the mail notifications.
+mod notifications;
+
+/// Contains the spam checks for public forms.
+mod spam;
+
+/// Contains the file type detection and image resizing of the media library.
+mod media;
+
+/// Contains the command line interface.
+mod cli;
+
 /// Contains all the routes.
 mod routes;
 
+use std::env;
+use std::process;
 use rocket_contrib::Template;
 use context_builder::Metadata;
+use config::Config;
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(status) = cli::run(&args) {
+        process::exit(status);
+    }
+
+    let config = Config::load().expect("load Satellite.toml");
+    let shortcodes = shortcodes::registry().expect("load shortcode templates");
+    if config.highlighting.enabled {
+        if let Err(err) = markdown::highlight::write_css(&config.highlighting) {
+            // TODO add real logging here
+            println!("Error writing the highlighting css: {}", err);
+        }
+    }
+
+    let pool = db::init_pool();
+    let renderer = markdown::Renderer::new(&config, shortcodes);
+    render_missing_posts(&pool, &renderer);
+
     // TODO make this more extensible
     let rocket = rocket::ignite() // _
         .attach(Template::fairing())
         .attach(Metadata::fairing())
-        .manage(db::init_pool());
+        .manage(pool)
+        .manage(spam::filter(&config.spam))
+        .manage(renderer)
+        .manage(config);
 
     // TODO make this better
     let rocket = routes::mount_to(rocket);
@@ -56,3 +120,22 @@ fn main() {
 
     rocket.launch();
 }
+
+/// Renders the posts that don't have html yet, e.g. after a migration added a column for it.
+/// The server is started anyway, so errors are only logged.
+fn render_missing_posts(pool: &db::Pool, renderer: &markdown::Renderer) {
+    let result = pool.get()
+        .map_err(|err| format!("{:?}", err))
+        .and_then(|conn| {
+            controllers::posts::render_missing(&db::DbConn(conn), renderer).map_err(|err| format!("{:?}", err))
+        });
+
+    match result {
+        Ok(0) => {},
+        Ok(count) => println!("Rendered {} posts without html.", count),
+        Err(err) => {
+            // TODO add real logging here
+            println!("Error rendering posts without html: {}", err);
+        },
+    }
+}
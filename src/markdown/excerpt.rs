@@ -0,0 +1,60 @@
+/// Marker that ends the excerpt of a post, everything before it is shown on listing pages.
+pub const MORE_MARKER: &'static str = "<!--more-->";
+
+/// Returns the part of the markdown that is shown on listing pages: everything before the
+/// [`MORE_MARKER`] or, if there is none, the first `words` words.
+///
+/// Shortcodes are never cut in half, a shortcode that would be cut is left out.
+///
+/// [`MORE_MARKER`]: constant.MORE_MARKER.html
+pub fn excerpt(md: &str, words: usize) -> &str {
+    if let Some(end) = md.find(MORE_MARKER) {
+        return md[..end].trim_right();
+    }
+
+    let mut count = 0;
+    let mut in_word = false;
+    let mut end = md.len();
+
+    for (i, c) in md.char_indices() {
+        if c.is_whitespace() {
+            in_word = false;
+        } else if !in_word {
+            in_word = true;
+            count += 1;
+            if count > words {
+                end = i;
+                break;
+            }
+        }
+    }
+
+    let cut = &md[..end];
+    match cut.rfind("{{<") {
+        Some(open) if !cut[open..].contains(">}}") => cut[..open].trim_right(),
+        _ => cut.trim_right(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cut_at_more_marker() {
+        assert_eq!(excerpt("Intro *text*\n\n<!--more-->\n\nRest", 1), "Intro *text*");
+    }
+
+    #[test]
+    fn cut_after_words() {
+        assert_eq!(excerpt("one two\n\nthree  four", 3), "one two\n\nthree");
+        assert_eq!(excerpt("one two", 3), "one two");
+        assert_eq!(excerpt("", 3), "");
+    }
+
+    #[test]
+    fn keep_shortcodes_whole() {
+        assert_eq!(excerpt("one {{< post \"a b\" >}} two", 3), "one");
+        assert_eq!(excerpt("one {{< post a >}} two three", 5), "one {{< post a >}}");
+    }
+}
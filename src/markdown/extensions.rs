@@ -0,0 +1,208 @@
+use pulldown_cmark::{Event, Tag};
+
+/// Replaces text between pairs of `~~` by `<del>`, like GitHub flavored markdown.
+/// Both markers have to be in the same block and at the same level of inline markup,
+/// otherwise they are kept as they are. Code is not changed.
+pub fn strikethrough<'a, I: Iterator<Item = Event<'a>>>(events: I) -> Vec<Event<'a>> {
+    let mut result = Vec::new();
+    // number of open inline tags and of open code spans and blocks
+    let mut depth = 0;
+    let mut code = 0;
+    // position of the opening marker in `result` and the depth it was found at
+    let mut open: Option<(usize, usize)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(tag) => {
+                match tag {
+                    Tag::Code | Tag::CodeBlock(_) => code += 1,
+                    _ => {},
+                }
+                if is_inline(&tag) {
+                    depth += 1;
+                } else {
+                    open = None;
+                }
+                result.push(Event::Start(tag));
+            },
+            Event::End(tag) => {
+                match tag {
+                    Tag::Code | Tag::CodeBlock(_) => code -= 1,
+                    _ => {},
+                }
+                if is_inline(&tag) {
+                    depth -= 1;
+                    if open.map_or(false, |(_, level)| level > depth) {
+                        open = None;
+                    }
+                } else {
+                    open = None;
+                }
+                result.push(Event::End(tag));
+            },
+            Event::Text(text) => {
+                if code > 0 || !text.contains("~~") {
+                    result.push(Event::Text(text));
+                    continue;
+                }
+
+                let mut rest: &str = &text;
+
+                while let Some(start) = rest.find("~~") {
+                    let run = rest[start..].chars().take_while(|&c| c == '~').count();
+                    if run != 2 {
+                        result.push(Event::Text(rest[..start + run].to_string().into()));
+                        rest = &rest[start + run..];
+                        continue;
+                    }
+
+                    let before = rest[..start].chars().next_back();
+                    let after = rest[start + 2..].chars().next();
+
+                    if start > 0 {
+                        result.push(Event::Text(rest[..start].to_string().into()));
+                    }
+
+                    match open {
+                        Some((position, level)) if level == depth && !before.map_or(false, char::is_whitespace) => {
+                            result[position] = Event::InlineHtml("<del>".into());
+                            result.push(Event::InlineHtml("</del>".into()));
+                            open = None;
+                        },
+                        _ => {
+                            if open.is_none() && !after.map_or(false, char::is_whitespace) {
+                                open = Some((result.len(), depth));
+                            }
+                            result.push(Event::Text("~~".into()));
+                        },
+                    }
+
+                    rest = &rest[start + 2..];
+                }
+
+                if !rest.is_empty() {
+                    result.push(Event::Text(rest.to_string().into()));
+                }
+            },
+            event => result.push(event),
+        }
+    }
+
+    result
+}
+
+fn is_inline(tag: &Tag) -> bool {
+    match *tag {
+        Tag::Emphasis | Tag::Strong | Tag::Code | Tag::Link(..) | Tag::Image(..) => true,
+        _ => false,
+    }
+}
+
+/// Turns list items starting with `[ ]` or `[x]` into disabled checkboxes, like GitHub flavored
+/// markdown.
+pub fn task_lists<'a, I: Iterator<Item = Event<'a>>>(events: I) -> Vec<Event<'a>> {
+    let mut result = Vec::new();
+    // whether nothing but the start of a paragraph came after the start of the current item
+    let mut item_start = false;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Item) => {
+                item_start = true;
+                result.push(Event::Start(Tag::Item));
+            },
+            Event::Start(Tag::Paragraph) if item_start => {
+                result.push(Event::Start(Tag::Paragraph));
+            },
+            Event::Text(text) => {
+                let marker = if item_start { task(&text) } else { None };
+                item_start = false;
+
+                match marker {
+                    Some(checked) => {
+                        let checkbox = if checked {
+                            "<input type=\"checkbox\" disabled=\"\" checked=\"\">"
+                        } else {
+                            "<input type=\"checkbox\" disabled=\"\">"
+                        };
+                        result.push(Event::InlineHtml(checkbox.into()));
+                        result.push(Event::Text(text[3..].to_string().into()));
+                    },
+                    None => result.push(Event::Text(text)),
+                }
+            },
+            event => {
+                item_start = false;
+                result.push(event);
+            },
+        }
+    }
+
+    result
+}
+
+/// Returns whether the task is done if the text starts with a task marker.
+/// The marker is always three bytes long.
+fn task(text: &str) -> Option<bool> {
+    let checked = if text.starts_with("[ ] ") {
+        false
+    } else if text.starts_with("[x] ") || text.starts_with("[X] ") {
+        true
+    } else {
+        return None;
+    };
+
+    Some(checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{Parser, html};
+
+    fn render(md: &str) -> String {
+        let events = task_lists(strikethrough(Parser::new(md)).into_iter());
+
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+        output
+    }
+
+    #[test]
+    fn strike_through_text() {
+        assert_eq!(render("~~old~~ new"), "<p><del>old</del> new</p>\n");
+        assert_eq!(render("a ~~b *c* d~~ e"), "<p>a <del>b <em>c</em> d</del> e</p>\n");
+        assert_eq!(render("*~~a~~*"), "<p><em><del>a</del></em></p>\n");
+    }
+
+    #[test]
+    fn keep_unmatched_markers() {
+        assert_eq!(render("~~open"), "<p>~~open</p>\n");
+        assert_eq!(render("~~a\n\nb~~"), "<p>~~a</p>\n<p>b~~</p>\n");
+        assert_eq!(render("a ~~ b ~~ c"), "<p>a ~~ b ~~ c</p>\n");
+        assert_eq!(render("a ~~~b~~~ c"), "<p>a ~~~b~~~ c</p>\n");
+        assert_eq!(render("~~a *b~~ c*"), "<p>~~a <em>b~~ c</em></p>\n");
+    }
+
+    #[test]
+    fn keep_code() {
+        assert_eq!(render("`~~a~~`"), "<p><code>~~a~~</code></p>\n");
+        assert_eq!(render("```\n~~a~~\n```\n"), "<pre><code>~~a~~\n</code></pre>\n");
+    }
+
+    #[test]
+    fn render_task_lists() {
+        assert_eq!(
+            render("- [x] done\n- [ ] todo\n- [a] no task\n"),
+            "<ul>\n<li><input type=\"checkbox\" disabled=\"\" checked=\"\"> done</li>\n\
+             <li><input type=\"checkbox\" disabled=\"\"> todo</li>\n\
+             <li>[a] no task</li>\n</ul>\n"
+        );
+        assert_eq!(
+            render("1. [X] a\n\n2. [ ] b\n"),
+            "<ol>\n<li>\n<p><input type=\"checkbox\" disabled=\"\" checked=\"\"> a</p>\n</li>\n\
+             <li>\n<p><input type=\"checkbox\" disabled=\"\"> b</p>\n</li>\n</ol>\n"
+        );
+        assert_eq!(render("[x] not in a list"), "<p>[x] not in a list</p>\n");
+    }
+}
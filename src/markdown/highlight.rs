@@ -0,0 +1,125 @@
+use std::fs::File;
+use std::io::{self, Write};
+
+use pulldown_cmark::{Event, Tag};
+use syntect::highlighting::ThemeSet;
+use syntect::html::{ClassedHTMLGenerator, css_for_theme};
+use syntect::parsing::SyntaxSet;
+
+use config::HighlightConfig;
+
+thread_local! {
+    // `SyntaxSet` is neither `Send` nor `Sync`, so every worker thread loads its own copy.
+    static SYNTAX_SET: SyntaxSet = SyntaxSet::load_defaults_nonewlines();
+}
+
+/// Replaces fenced code blocks with a known language tag by highlighted html.
+/// The html only contains classes, the colors come from the css written by [`write_css`].
+///
+/// [`write_css`]: fn.write_css.html
+pub fn code_blocks<'a, I: Iterator<Item = Event<'a>>>(events: I) -> Vec<Event<'a>> {
+    let mut result = Vec::new();
+    // language and content of the code block that is currently highlighted
+    let mut current: Option<(String, String)> = None;
+
+    for event in events {
+        current = match (current, event) {
+            (None, Event::Start(Tag::CodeBlock(lang))) => {
+                let lang = language(&lang);
+                if lang.is_empty() || !has_syntax(&lang) {
+                    result.push(Event::Start(Tag::CodeBlock(lang.into())));
+                    None
+                } else {
+                    Some((lang, String::new()))
+                }
+            },
+            (Some((lang, mut code)), Event::Text(text)) => {
+                code.push_str(&text);
+                Some((lang, code))
+            },
+            (Some((lang, code)), Event::End(Tag::CodeBlock(_))) => {
+                result.push(Event::Html(highlight(&lang, &code).into()));
+                None
+            },
+            (current, event) => {
+                result.push(event);
+                current
+            },
+        };
+    }
+
+    result
+}
+
+/// Returns the language of an info string like `rust,ignore`.
+fn language(info: &str) -> String {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .next()
+        .unwrap_or("")
+        .to_string()
+}
+
+fn has_syntax(lang: &str) -> bool {
+    SYNTAX_SET.with(|syntax_set| syntax_set.find_syntax_by_token(lang).is_some())
+}
+
+/// Highlights `code` as `lang`, which has to be a language known to syntect.
+fn highlight(lang: &str, code: &str) -> String {
+    SYNTAX_SET.with(|syntax_set| {
+        let syntax = syntax_set.find_syntax_by_token(lang).unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+        let mut generator = ClassedHTMLGenerator::new(syntax);
+        for line in code.lines() {
+            generator.parse_html_for_line(line);
+        }
+
+        format!("<pre class=\"code\"><code>{}</code></pre>\n", generator.finalize())
+    })
+}
+
+/// Writes the css for the configured theme to the configured file, so it can be served with the
+/// other static files.
+pub fn write_css(config: &HighlightConfig) -> io::Result<()> {
+    let themes = ThemeSet::load_defaults();
+    let theme = themes.themes.get(&config.theme).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("unknown theme '{}'", config.theme))
+    })?;
+
+    File::create(&config.css)?.write_all(css_for_theme(theme).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{Parser, html};
+
+    fn render(md: &str) -> String {
+        let mut output = String::new();
+        html::push_html(&mut output, code_blocks(Parser::new(md)).into_iter());
+        output
+    }
+
+    #[test]
+    fn highlight_known_languages() {
+        let output = render("```rust\nfn main() {}\n```\n");
+
+        assert!(output.starts_with("<pre class=\"code\"><code><span class=\"source rust\">"));
+        assert!(output.contains("fn</span>"));
+    }
+
+    #[test]
+    fn escape_highlighted_code() {
+        let output = render("```rust,ignore\nlet x = \"<script>\";\n```\n");
+
+        assert!(output.contains("&lt;script&gt;"));
+        assert!(!output.contains("<script>"));
+    }
+
+    #[test]
+    fn keep_other_code_blocks() {
+        assert_eq!(render("```\na < b\n```\n"), "<pre><code>a &lt; b\n</code></pre>\n");
+        assert_eq!(
+            render("```no-such-language\nx\n```\n"),
+            "<pre><code class=\"language-no-such-language\">x\n</code></pre>\n"
+        );
+    }
+}
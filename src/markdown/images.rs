@@ -0,0 +1,145 @@
+use pulldown_cmark::{Event, Tag};
+
+/// An image from the media library, see [`responsive`].
+///
+/// [`responsive`]: fn.responsive.html
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ResponsiveImage {
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+    /// Urls and widths of the scaled down copies, narrowest first.
+    pub sources: Vec<(String, u32)>,
+}
+
+/// Replaces the images `lookup` knows by `<img>` tags with `width` and `height`, so browsers can
+/// reserve the space before the image is loaded, and a `srcset` of the scaled down copies.
+/// `sizes` is the value of the `sizes` attribute.
+pub fn responsive<'a, I, F>(events: I, sizes: &str, lookup: F) -> Vec<Event<'a>>
+where
+    I: Iterator<Item = Event<'a>>,
+    F: Fn(&str) -> Option<ResponsiveImage>,
+{
+    let mut result = Vec::new();
+    // the image that is currently replaced with its title and alt text
+    let mut current: Option<(ResponsiveImage, String, String)> = None;
+
+    for event in events {
+        current = match (current, event) {
+            (None, Event::Start(Tag::Image(url, title))) => match lookup(&*url) {
+                Some(image) => Some((image, title.into_owned(), String::new())),
+                None => {
+                    result.push(Event::Start(Tag::Image(url, title)));
+                    None
+                },
+            },
+            (Some((image, title, mut alt)), Event::Text(text)) => {
+                alt.push_str(&text);
+                Some((image, title, alt))
+            },
+            (Some((image, title, alt)), Event::End(Tag::Image(..))) => {
+                result.push(Event::InlineHtml(html(&image, &title, &alt, sizes).into()));
+                None
+            },
+            // like pulldown_cmark, only the text of the alt text is kept
+            (Some(current), _) => Some(current),
+            (None, event) => {
+                result.push(event);
+                None
+            },
+        };
+    }
+
+    result
+}
+
+fn html(image: &ResponsiveImage, title: &str, alt: &str, sizes: &str) -> String {
+    let mut html = format!("<img src=\"{}\" alt=\"{}\"", escape(&image.url), escape(alt));
+
+    if !title.is_empty() {
+        html.push_str(&format!(" title=\"{}\"", escape(title)));
+    }
+    html.push_str(&format!(" width=\"{}\" height=\"{}\"", image.width, image.height));
+
+    if !image.sources.is_empty() {
+        let srcset: Vec<String> = image.sources.iter()
+            .map(|&(ref url, width)| format!("{} {}w", url, width))
+            .chain(Some(format!("{} {}w", image.url, image.width)))
+            .collect();
+        html.push_str(&format!(" srcset=\"{}\" sizes=\"{}\"", escape(&srcset.join(", ")), escape(sizes)));
+    }
+
+    html.push('>');
+    html
+}
+
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{Parser, html};
+
+    fn render(md: &str) -> String {
+        let events = responsive(Parser::new(md), "100vw", |url| {
+            match url {
+                "/media/cat.jpg" => Some(ResponsiveImage {
+                    url: url.to_string(),
+                    width: 1600,
+                    height: 1200,
+                    sources: vec![
+                        ("/media/cat.thumbnail.jpg".to_string(), 150),
+                        ("/media/cat.medium.jpg".to_string(), 640),
+                    ],
+                }),
+                "/media/anim.gif" => Some(ResponsiveImage {
+                    url: url.to_string(),
+                    width: 300,
+                    height: 200,
+                    sources: Vec::new(),
+                }),
+                _ => None,
+            }
+        });
+
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+        output
+    }
+
+    #[test]
+    fn adds_srcset_to_media() {
+        assert_eq!(
+            render("![A *cat*](/media/cat.jpg \"Meow\")"),
+            "<p><img src=\"/media/cat.jpg\" alt=\"A cat\" title=\"Meow\" width=\"1600\" height=\"1200\" \
+             srcset=\"/media/cat.thumbnail.jpg 150w, /media/cat.medium.jpg 640w, /media/cat.jpg 1600w\" \
+             sizes=\"100vw\"></p>\n"
+        );
+    }
+
+    #[test]
+    fn only_adds_size_without_variants() {
+        assert_eq!(
+            render("![](/media/anim.gif)"),
+            "<p><img src=\"/media/anim.gif\" alt=\"\" width=\"300\" height=\"200\"></p>\n"
+        );
+    }
+
+    #[test]
+    fn keeps_other_images() {
+        let html = render("![dog](https://example.com/dog.png)");
+        assert!(html.contains("src=\"https://example.com/dog.png\""));
+        assert!(!html.contains("width"));
+        assert!(!html.contains("srcset"));
+    }
+
+    #[test]
+    fn escapes_alt_text() {
+        assert!(render("![5 > 3 & \"x\"](/media/anim.gif)").contains("alt=\"5 &gt; 3 &amp; &quot;x&quot;\""));
+    }
+}
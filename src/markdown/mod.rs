@@ -0,0 +1,174 @@
+pub mod excerpt;
+pub mod extensions;
+pub mod highlight;
+pub mod images;
+pub mod sanitize;
+pub mod toc;
+
+use pulldown_cmark::{Parser, html, Options};
+
+use config::{Config, MarkdownConfig};
+use controllers::media;
+use db::DbConn;
+use shortcodes::Shortcodes;
+use self::images::ResponsiveImage;
+use self::sanitize::Sanitizer;
+
+/// Renders the markdown of posts and pages to html that is safe to include in templates.
+/// Needs to be managed by rocket.
+pub struct Renderer {
+    sanitizer: Sanitizer,
+    highlight: bool,
+    markdown: MarkdownConfig,
+    shortcodes: Shortcodes,
+    /// The `sizes` attribute of images from the media library.
+    sizes: String,
+}
+
+/// The output of [`Renderer::render`].
+///
+/// [`Renderer::render`]: struct.Renderer.html#method.render
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Rendered {
+    pub html: String,
+    /// A nested list linking to the headings, empty if the table of contents is disabled or
+    /// the document is too short.
+    pub toc: String,
+}
+
+impl Renderer {
+    /// Creates a renderer configured by the `[markdown]`, `[sanitizer]`, `[highlighting]` and
+    /// `[uploads]` sections.
+    pub fn new(config: &Config, shortcodes: Shortcodes) -> Renderer {
+        Renderer {
+            sanitizer: Sanitizer::new(&config.sanitizer),
+            highlight: config.highlighting.enabled,
+            markdown: config.markdown.clone(),
+            shortcodes,
+            sizes: config.uploads.sizes.clone(),
+        }
+    }
+
+    /// Renders the excerpt of a post: `explicit` if it isn't empty, otherwise the start of the
+    /// `body` as returned by [`excerpt::excerpt`].
+    ///
+    /// [`excerpt::excerpt`]: excerpt/fn.excerpt.html
+    pub fn render_excerpt(&self, db: &DbConn, explicit: &str, body: &str) -> Result<String, Vec<String>> {
+        let md = if explicit.trim().is_empty() {
+            excerpt::excerpt(body, self.markdown.excerpt_words)
+        } else {
+            explicit
+        };
+
+        self.render_with_shortcodes(db, md).map(|rendered| rendered.html)
+    }
+
+    /// Like [`render`], but expands shortcodes first and adds the size and scaled down copies to
+    /// images from the media library.
+    /// Returns an error message for every shortcode that couldn't be rendered.
+    ///
+    /// [`render`]: #method.render
+    pub fn render_with_shortcodes(&self, db: &DbConn, md: &str) -> Result<Rendered, Vec<String>> {
+        let expanded = self.shortcodes.expand(db, md)?;
+        let rendered = self.render_with_images(expanded.text(), |url| media::responsive_image(db, url));
+
+        Ok(Rendered {
+            html: expanded.insert_into(&rendered.html),
+            toc: rendered.toc,
+        })
+    }
+
+    /// Parses markdown to html using pulldown_cmark, adds anchors to the headings, strikethrough
+    /// and task lists, highlights code blocks and removes everything the sanitizer doesn't allow.
+    pub fn render(&self, md: &str) -> Rendered {
+        self.render_with_images(md, |_| None)
+    }
+
+    /// Renders the images known to `lookup` with [`images::responsive`].
+    ///
+    /// [`images::responsive`]: images/fn.responsive.html
+    fn render_with_images<F>(&self, md: &str, lookup: F) -> Rendered
+    where
+        F: Fn(&str) -> Option<ResponsiveImage>,
+    {
+        let mut options = Options::empty();
+        options.insert(Options::ENABLE_TABLES);
+        options.insert(Options::ENABLE_FOOTNOTES);
+
+        let (events, headings) = toc::add_anchors(Parser::new_ext(md, options));
+        // pulldown_cmark 0.2 doesn't support these yet
+        let events = extensions::strikethrough(events.into_iter());
+        let events = extensions::task_lists(events.into_iter());
+        let events = if self.highlight {
+            highlight::code_blocks(events.into_iter())
+        } else {
+            events
+        };
+        let events = images::responsive(events.into_iter(), &self.sizes, lookup);
+
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+
+        let toc = if self.markdown.table_of_contents && headings.len() >= self.markdown.toc_min_headings {
+            toc::html(&headings)
+        } else {
+            String::new()
+        };
+
+        Rendered {
+            html: self.sanitizer.clean(&output),
+            toc,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tera::Tera;
+
+    fn renderer() -> Renderer {
+        Renderer::new(&Config::default(), Shortcodes::new(Tera::default()))
+    }
+
+    #[test]
+    fn sanitize_inline_html() {
+        let renderer = renderer();
+
+        assert_eq!(renderer.render("*hi*<script>alert(1)</script>").html, "<p><em>hi</em></p>\n");
+        assert_eq!(
+            renderer.render("[x](javascript:alert(1))").html,
+            "<p><a rel=\"noopener noreferrer\">x</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn render_extensions() {
+        let renderer = renderer();
+
+        assert_eq!(renderer.render("~~old~~").html, "<p><del>old</del></p>\n");
+        let tasks = renderer.render("- [x] done\n- [ ] todo\n").html;
+        assert_eq!(tasks.matches("type=\"checkbox\"").count(), 2);
+        assert_eq!(tasks.matches("checked").count(), 1);
+    }
+
+    #[test]
+    fn render_tables() {
+        let html = renderer().render("| a | b |\n|---|---|\n| 1 | 2 |\n").html;
+
+        assert!(html.contains("<table>"));
+        assert_eq!(html.matches("<td>").count(), 2);
+    }
+
+    #[test]
+    fn only_add_toc_to_long_documents() {
+        let renderer = renderer();
+
+        assert_eq!(renderer.render("# A\n## B\n").toc, "");
+        assert_eq!(
+            renderer.render("# A\n## B\n## C\n").toc,
+            "<ul><li><a href=\"#a\">A</a><ul><li><a href=\"#b\">B</a></li>\
+             <li><a href=\"#c\">C</a></li></ul></li></ul>"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::{HashMap, HashSet};
+
+use ammonia::Builder;
+
+use config::SanitizerConfig;
+
+/// Removes everything from rendered html that is not on the allowlist of the `[sanitizer]`
+/// section, so posts and pages can't run scripts in the browsers of readers.
+///
+/// Elements that are not allowed are removed, but their content is kept, except for the content
+/// of `script` and `style` elements. Urls with a scheme that is not allowed are removed,
+/// relative urls are kept.
+#[derive(Debug, Clone)]
+pub struct Sanitizer {
+    tags: HashSet<String>,
+    tag_attributes: HashMap<String, HashSet<String>>,
+    generic_attributes: HashSet<String>,
+    url_schemes: HashSet<String>,
+}
+
+impl Sanitizer {
+    pub fn new(config: &SanitizerConfig) -> Sanitizer {
+        Sanitizer {
+            tags: config.tags.iter().cloned().collect(),
+            tag_attributes: config.attributes
+                .iter()
+                .map(|(tag, attributes)| (tag.clone(), attributes.iter().cloned().collect()))
+                .collect(),
+            generic_attributes: config.generic_attributes.iter().cloned().collect(),
+            url_schemes: config.url_schemes.iter().cloned().collect(),
+        }
+    }
+
+    pub fn clean(&self, html: &str) -> String {
+        let mut builder = Builder::default();
+        builder
+            .tags(self.tags.iter().map(|tag| tag.as_str()).collect())
+            .tag_attributes(
+                self.tag_attributes
+                    .iter()
+                    .map(|(tag, attributes)| {
+                        (tag.as_str(), attributes.iter().map(|attribute| attribute.as_str()).collect())
+                    })
+                    .collect(),
+            )
+            .generic_attributes(self.generic_attributes.iter().map(|attribute| attribute.as_str()).collect())
+            .url_schemes(self.url_schemes.iter().map(|scheme| scheme.as_str()).collect());
+
+        builder.clean(html).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean(html: &str) -> String {
+        Sanitizer::new(&SanitizerConfig::default()).clean(html)
+    }
+
+    #[test]
+    fn keep_allowed_markup() {
+        assert_eq!(
+            clean("<p><strong>Hello</strong> <a href=\"https://example.com\">world</a></p>"),
+            "<p><strong>Hello</strong> <a href=\"https://example.com\" rel=\"noopener noreferrer\">world</a></p>"
+        );
+        assert_eq!(clean("<a href=\"/post/hello\">x</a>"), "<a href=\"/post/hello\" rel=\"noopener noreferrer\">x</a>");
+    }
+
+    #[test]
+    fn remove_scripts() {
+        assert_eq!(clean("<script>alert(1)</script>"), "");
+        assert_eq!(clean("<p>a<script src=\"https://evil.example/x.js\"></script>b</p>"), "<p>ab</p>");
+        assert_eq!(clean("<style>body { display: none }</style>"), "");
+    }
+
+    #[test]
+    fn remove_event_handlers() {
+        assert_eq!(clean("<img src=\"x.png\" onerror=\"alert(1)\">"), "<img src=\"x.png\">");
+        assert_eq!(clean("<p onclick=\"alert(1)\">x</p>"), "<p>x</p>");
+        assert_eq!(clean("<svg onload=\"alert(1)\"></svg>"), "");
+    }
+
+    #[test]
+    fn remove_dangerous_urls() {
+        assert_eq!(clean("<a href=\"javascript:alert(1)\">x</a>"), "<a rel=\"noopener noreferrer\">x</a>");
+        assert_eq!(clean("<a href=\"JaVaScRiPt:alert(1)\">x</a>"), "<a rel=\"noopener noreferrer\">x</a>");
+        assert_eq!(clean("<a href=\"data:text/html;base64,PHNjcmlwdD4=\">x</a>"), "<a rel=\"noopener noreferrer\">x</a>");
+        assert_eq!(clean("<img src=\"javascript:alert(1)\">"), "<img>");
+    }
+
+    #[test]
+    fn remove_disallowed_elements() {
+        assert_eq!(clean("<iframe src=\"https://evil.example\"></iframe>"), "");
+        assert_eq!(clean("<form action=\"/logout\"><button>x</button></form>"), "x");
+        assert_eq!(clean("<p style=\"position: fixed\">x</p>"), "<p>x</p>");
+    }
+
+    #[test]
+    fn respect_configured_allowlist() {
+        let config = SanitizerConfig {
+            tags: vec!["p".to_string()],
+            attributes: HashMap::new(),
+            generic_attributes: Vec::new(),
+            url_schemes: Vec::new(),
+        };
+        let sanitizer = Sanitizer::new(&config);
+
+        assert_eq!(sanitizer.clean("<p><em>x</em></p>"), "<p>x</p>");
+    }
+}
@@ -0,0 +1,174 @@
+use std::cmp;
+use std::collections::HashSet;
+
+use pulldown_cmark::{Event, Tag};
+
+use slug;
+
+/// A heading of a rendered document, used to build the table of contents.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Heading {
+    pub level: i32,
+    /// The `id` attribute of the heading, unique within the document.
+    pub id: String,
+    /// The text of the heading without any markup.
+    pub text: String,
+}
+
+/// Gives every heading an `id` made from its text, so it can be linked to.
+/// Returns the changed events and all headings in document order.
+pub fn add_anchors<'a, I: Iterator<Item = Event<'a>>>(events: I) -> (Vec<Event<'a>>, Vec<Heading>) {
+    let mut result = Vec::new();
+    let mut headings = Vec::new();
+    let mut used_ids = HashSet::new();
+    // level, position of the start tag in `result` and text of the heading that is currently open
+    let mut current: Option<(i32, usize, String)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Header(level)) => {
+                current = Some((level, result.len(), String::new()));
+                result.push(Event::Start(Tag::Header(level)));
+            },
+            Event::End(Tag::Header(level)) => {
+                if let Some((level, start, text)) = current.take() {
+                    let id = unique_id(&text, &mut used_ids);
+                    result[start] = Event::Html(format!("<h{} id=\"{}\">", level, id).into());
+                    headings.push(Heading { level, id, text });
+                }
+                result.push(Event::End(Tag::Header(level)));
+            },
+            Event::Text(text) => {
+                if let Some((_, _, ref mut heading)) = current {
+                    heading.push_str(&text);
+                }
+                result.push(Event::Text(text));
+            },
+            event => result.push(event),
+        }
+    }
+
+    (result, headings)
+}
+
+/// Creates an id from the heading text that is not in `used` yet and adds it.
+fn unique_id(text: &str, used: &mut HashSet<String>) -> String {
+    let mut base = slug::slugify(text);
+    if base.is_empty() {
+        base = "section".to_string();
+    }
+
+    let mut id = base.clone();
+    let mut counter = 1;
+    while used.contains(&id) {
+        id = format!("{}-{}", base, counter);
+        counter += 1;
+    }
+
+    used.insert(id.clone());
+    id
+}
+
+/// Builds a nested list of links to the headings.
+/// The highest heading level becomes the top level of the list, skipped levels are left out.
+pub fn html(headings: &[Heading]) -> String {
+    let top = match headings.iter().map(|heading| heading.level).min() {
+        Some(top) => top,
+        None => return String::new(),
+    };
+
+    let mut html = String::new();
+    let mut depth = 0;
+
+    for heading in headings {
+        let level = cmp::min((heading.level - top + 1) as usize, depth + 1);
+
+        if level > depth {
+            html.push_str("<ul>");
+            depth = level;
+        } else {
+            html.push_str("</li>");
+            while depth > level {
+                html.push_str("</ul></li>");
+                depth -= 1;
+            }
+        }
+
+        html.push_str(&format!("<li><a href=\"#{}\">{}</a>", heading.id, escape(&heading.text)));
+    }
+
+    while depth > 0 {
+        html.push_str("</li></ul>");
+        depth -= 1;
+    }
+
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{Parser, html};
+
+    fn render(md: &str) -> (String, Vec<Heading>) {
+        let (events, headings) = add_anchors(Parser::new(md));
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+        (output, headings)
+    }
+
+    fn heading(level: i32, id: &str, text: &str) -> Heading {
+        Heading {
+            level,
+            id: id.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn add_ids_to_headings() {
+        let (output, headings) = render("## Getting *started*\n\ntext\n");
+
+        assert_eq!(output, "<h2 id=\"getting-started\">Getting <em>started</em></h2>\n<p>text</p>\n");
+        assert_eq!(headings, vec![heading(2, "getting-started", "Getting started")]);
+    }
+
+    #[test]
+    fn make_ids_unique() {
+        let (_, headings) = render("# Usage\n# Usage\n# Usage\n# ?\n");
+        let ids: Vec<&str> = headings.iter().map(|heading| heading.id.as_str()).collect();
+
+        assert_eq!(ids, vec!["usage", "usage-1", "usage-2", "section"]);
+    }
+
+    #[test]
+    fn nest_table_of_contents() {
+        let headings = vec![
+            heading(2, "a", "A"),
+            heading(3, "b", "B"),
+            heading(3, "c", "C <3"),
+            heading(2, "d", "D"),
+        ];
+
+        assert_eq!(
+            html(&headings),
+            "<ul><li><a href=\"#a\">A</a><ul><li><a href=\"#b\">B</a></li>\
+             <li><a href=\"#c\">C &lt;3</a></li></ul></li><li><a href=\"#d\">D</a></li></ul>"
+        );
+    }
+
+    #[test]
+    fn skip_missing_levels() {
+        let headings = vec![heading(1, "a", "A"), heading(4, "b", "B")];
+
+        assert_eq!(html(&headings), "<ul><li><a href=\"#a\">A</a><ul><li><a href=\"#b\">B</a></li></ul></li></ul>");
+        assert_eq!(html(&[]), "");
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+
+use image::{self, gif, jpeg, png, webp, DynamicImage, FilterType, GenericImage, ImageDecoder, ImageError, ImageResult};
+
+use slug;
+
+/// File signatures of the types that can be uploaded, see [`sniff`].
+///
+/// [`sniff`]: fn.sniff.html
+const SIGNATURES: [(&'static [u8], &'static str); 5] = [
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"RIFF", "image/webp"),
+];
+
+/// Detects the MIME type of a file from its first bytes.
+/// The name and the content type sent by the browser are not trusted, so a script renamed to
+/// `cat.png` is rejected.
+pub fn sniff(data: &[u8]) -> Option<&'static str> {
+    SIGNATURES.iter()
+        .find(|&&(signature, mime)| {
+            // RIFF is a container, the format follows the size of the file.
+            data.starts_with(signature) && (mime != "image/webp" || data.get(8..12) == Some(&b"WEBP"[..]))
+        })
+        .map(|&(_, mime)| mime)
+}
+
+/// Returns the file extension used for uploads of the given MIME type.
+pub fn extension(mime: &str) -> Option<&'static str> {
+    match mime {
+        "image/png" => Some("png"),
+        "image/jpeg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        _ => None,
+    }
+}
+
+/// An uploaded image and the scaled down copies written by [`resize`].
+///
+/// [`resize`]: fn.resize.html
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Resized {
+    pub width: u32,
+    pub height: u32,
+    pub variants: Vec<Variant>,
+}
+
+/// A scaled down copy of an image.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Variant {
+    pub name: String,
+    pub filename: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Decodes the image stored at `path` and writes a copy next to it for every entry of
+/// `variants` that is narrower than the image, named `<stem>.<name>.<extension>`.
+/// Slugs never contain dots, so these names can't collide with other uploads.
+///
+/// Copies are only written for PNG and JPEG images. GIFs would lose their animation and WebP
+/// can't be encoded, but their size is still returned.
+///
+/// The size is read from the header first and images with more than `max_pixels` pixels are
+/// never decoded, so a small file can't make the server allocate gigabytes. Only their size is
+/// returned.
+pub fn resize(path: &Path, data: &[u8], mime: &str, variants: &HashMap<String, u32>, max_pixels: u64) -> ImageResult<Resized> {
+    let (width, height) = dimensions(data, mime)?;
+    let mut resized = Resized { width, height, variants: Vec::new() };
+
+    if mime != "image/png" && mime != "image/jpeg" || width as u64 * height as u64 > max_pixels {
+        return Ok(resized);
+    }
+
+    let image = image::load_from_memory(data)?;
+
+    let (stem, ext) = match (path.file_stem().and_then(|s| s.to_str()), path.extension().and_then(|s| s.to_str())) {
+        (Some(stem), Some(ext)) => (stem, ext),
+        _ => return Ok(resized),
+    };
+
+    for (name, &max_width) in variants {
+        let name = slug::slugify(name);
+        if name.is_empty() || max_width == 0 || max_width >= width {
+            continue;
+        }
+
+        let variant = scale(&image, max_width);
+        let filename = format!("{}.{}.{}", stem, name, ext);
+        variant.save(path.with_file_name(&filename))?;
+
+        resized.variants.push(Variant {
+            name,
+            filename,
+            width: variant.width(),
+            height: variant.height(),
+        });
+    }
+
+    resized.variants.sort_by_key(|variant| variant.width);
+    Ok(resized)
+}
+
+/// Reads the size of an image from its header without decoding it.
+fn dimensions(data: &[u8], mime: &str) -> ImageResult<(u32, u32)> {
+    let data = Cursor::new(data);
+    match mime {
+        "image/png" => png::PNGDecoder::new(data).dimensions(),
+        "image/jpeg" => jpeg::JPEGDecoder::new(data).dimensions(),
+        "image/gif" => gif::Decoder::new(data).dimensions(),
+        "image/webp" => webp::WebpDecoder::new(data).dimensions(),
+        _ => Err(ImageError::UnsupportedError(format!("Can't read the size of {} files", mime))),
+    }
+}
+
+fn scale(image: &DynamicImage, width: u32) -> DynamicImage {
+    let height = scaled_height(image.dimensions(), width);
+    image.resize_exact(width, height, FilterType::Lanczos3)
+}
+
+/// Returns the height of an image of the given size scaled to `width`, keeping the aspect ratio.
+fn scaled_height((width, height): (u32, u32), new_width: u32) -> u32 {
+    let scaled = (height as u64 * new_width as u64 + width as u64 / 2) / width as u64;
+    if scaled == 0 { 1 } else { scaled as u32 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_images() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(sniff(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(sniff(b"GIF89a\x01\0\x01\0"), Some("image/gif"));
+        assert_eq!(sniff(b"GIF87a\x01\0\x01\0"), Some("image/gif"));
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WEBPVP8 "), Some("image/webp"));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(sniff(b""), None);
+        assert_eq!(sniff(b"<script>alert(1)</script>"), None);
+        assert_eq!(sniff(b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>"), None);
+        assert_eq!(sniff(b"%PDF-1.4"), None);
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WAVEfmt "), None);
+        assert_eq!(sniff(b"\x89PNG"), None);
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(extension("image/png"), Some("png"));
+        assert_eq!(extension("image/jpeg"), Some("jpg"));
+        assert_eq!(extension("text/html"), None);
+    }
+
+    #[test]
+    fn scaled_heights_keep_the_aspect_ratio() {
+        assert_eq!(scaled_height((1920, 1080), 640), 360);
+        assert_eq!(scaled_height((1000, 333), 150), 50);
+        assert_eq!(scaled_height((800, 1200), 640), 960);
+        assert_eq!(scaled_height((4000, 10), 150), 1);
+    }
+
+    #[test]
+    fn large_images_are_not_resized() {
+        let mut data = Vec::new();
+        png::PNGEncoder::new(&mut data).encode(&[0; 4 * 2 * 3], 4, 2, image::ColorType::RGB(8)).unwrap();
+
+        let dir = ::std::env::temp_dir();
+        let variants = hashmap!("small".to_string() => 2);
+
+        let resized = resize(&dir.join("large.png"), &data, "image/png", &variants, 4).unwrap();
+        assert_eq!(resized, Resized { width: 4, height: 2, variants: Vec::new() });
+        assert!(!dir.join("large.small.png").exists());
+
+        let resized = resize(&dir.join("small.png"), &data, "image/png", &variants, 8).unwrap();
+        assert_eq!(resized.variants.len(), 1);
+        assert_eq!((resized.variants[0].width, resized.variants[0].height), (2, 1));
+        assert!(dir.join("small.small.png").exists());
+    }
+}
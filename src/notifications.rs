@@ -0,0 +1,110 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use chrono::Utc;
+
+use config::NotificationConfig;
+use controllers::{comments, posts, users};
+use db::DbConn;
+use db::models::Comment;
+use routes::Urlify;
+
+/// A plain text mail.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    /// Formats the mail as a message that can be passed to sendmail.
+    fn to_message(&self, from: &str) -> String {
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
+            header_value(from),
+            header_value(&self.to),
+            header_value(&self.subject),
+            self.body,
+        )
+    }
+}
+
+/// Removes line breaks, so values can't add headers of their own.
+fn header_value(value: &str) -> String {
+    value.replace(|c: char| c == '\r' || c == '\n', " ")
+}
+
+/// Sends the mail the way the config says.
+pub fn send(config: &NotificationConfig, mail: &Mail) -> io::Result<()> {
+    let message = mail.to_message(&config.from);
+
+    if let Some(ref command) = config.command {
+        let mut parts = command.split_whitespace();
+        let program = parts.next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty notification command"))?;
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(message.as_bytes())?;
+        }
+
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(io::Error::new(io::ErrorKind::Other, format!("{} exited with {}", program, status)));
+        }
+    } else if let Some(ref spool) = config.spool {
+        fs::create_dir_all(spool)?;
+
+        let name = format!("{}.eml", Utc::now().format("%Y%m%d%H%M%S%f"));
+        File::create(Path::new(spool).join(name))?.write_all(message.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Tells the author of the post about a newly approved comment and, if it is a reply, the
+/// author of the parent comment. Nobody is told about their own comments.
+/// `base_url` is used to build the links in the mails.
+pub fn comment_approved(config: &NotificationConfig, base_url: &str, db: &DbConn, comment: &Comment) {
+    let post = match posts::get_any_with_id(db, comment.post_id) {
+        Ok(post) => post,
+        Err(_) => return,
+    };
+    let url = format!("{}{}#comment-{}", base_url.trim_right_matches('/'), post.url(), comment.id);
+    let mut mails = Vec::new();
+
+    if let Some(parent) = comment.parent_id.and_then(|id| comments::get_with_id(db, id).ok()) {
+        if parent.author_email != comment.author_email {
+            mails.push(Mail {
+                to: parent.author_email,
+                subject: format!("New reply to your comment on \"{}\"", post.title),
+                body: format!("{} replied to your comment:\n\n{}\n\n{}\n", comment.author_name, comment.body, url),
+            });
+        }
+    }
+
+    if let Some(author) = post.author_id.and_then(|id| users::get_with_id(db, id).ok()) {
+        let already_notified = mails.iter().any(|mail| mail.to == author.email);
+        if author.email != comment.author_email && !already_notified {
+            mails.push(Mail {
+                to: author.email,
+                subject: format!("New comment on \"{}\"", post.title),
+                body: format!("{} commented on your post:\n\n{}\n\n{}\n", comment.author_name, comment.body, url),
+            });
+        }
+    }
+
+    for mail in mails {
+        if let Err(err) = send(config, &mail) {
+            // TODO add real logging here
+            println!("Error sending notification to {}: {:?}", mail.to, err);
+        }
+    }
+}
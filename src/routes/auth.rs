@@ -0,0 +1,54 @@
+use rocket_contrib::Template;
+use rocket::Route;
+use rocket::response::{Redirect, Flash};
+
+use auth;
+use context_builder::ContextBuilder;
+use context_builder::csrf::{CsrfForm, NoFields};
+use db::DbConn;
+use db::models::User;
+use controllers::users::{self, Login};
+use forms::users::LoginForm;
+use routes::frontend::prepare_context_builder;
+
+pub fn routes() -> Vec<Route> {
+    routes![login_form, login, logout]
+}
+
+/// Shows the login form, or redirects to the backend if the visitor is already logged in.
+/// The `User` guard fails instead of forwarding when nobody is logged in, so this has to be a
+/// single route taking an `Option`.
+#[get("/login")]
+fn login_form(user: Option<User>, db: DbConn, mut context_builder: ContextBuilder<LoginForm>) -> Result<Redirect, Template> {
+    if user.is_some() {
+        return Ok(Redirect::to("/admin"));
+    }
+
+    prepare_context_builder(Some("/login"), &db, &mut context_builder);
+    let context = context_builder.finalize_with_default();
+    Err(Template::render("frontend/login", &context))
+}
+
+#[post("/login", data = "<login>")]
+fn login(db: DbConn, login: CsrfForm<Login>, mut context_builder: ContextBuilder<LoginForm>) -> Result<auth::Session<Flash<Redirect>>, Template> {
+    let login = login.into_inner();
+
+    match users::try_login(&db, &login) {
+        Ok(user) => {
+            let message = format!("Welcome back, {}.", user.display_name);
+            Ok(auth::login(&user, Flash::success(Redirect::to("/admin"), message)))
+        },
+        Err(errors) => {
+            prepare_context_builder(Some("/login"), &db, &mut context_builder);
+            let context = context_builder.finalize_with_data(
+                LoginForm::with_errors(login, errors)
+            );
+            Err(Template::render("frontend/login", &context))
+        }
+    }
+}
+
+#[post("/logout", data = "<_csrf>")]
+fn logout(_csrf: CsrfForm<NoFields>) -> auth::Session<Flash<Redirect>> {
+    auth::logout(Flash::success(Redirect::to("/"), "You have been logged out."))
+}
@@ -1,8 +0,0 @@
-use rocket::Route;
-//use rocket_contrib::Template;
-
-pub fn routes() -> Vec<Route> {
-    routes![]
-}
-
-// TODO add more routes
\ No newline at end of file
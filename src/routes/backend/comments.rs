@@ -0,0 +1,85 @@
+use rocket_contrib::Template;
+use rocket::{Route, State};
+use rocket::response::{Redirect, Failure, Flash};
+use rocket::http::Status;
+
+use auth::permissions::{RequirePermission, ModerateComments};
+use context_builder::{ContextBuilder, Metadata};
+use context_builder::csrf::{CsrfForm, NoFields};
+use config::Config;
+use db::DbConn;
+use db::models::{Comment, CommentStatus};
+use controllers::comments;
+use forms::comments::CommentFilter;
+use notifications;
+use super::prepare_context_builder;
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        comment_list,
+        comment_list_filtered,
+        approve_comment,
+        mark_comment_as_spam,
+        delete_comment
+    ]
+}
+
+/// Data for the `backend/comments/index` template.
+#[derive(Debug, Serialize)]
+struct CommentList {
+    comments: Vec<Comment>,
+    status: CommentStatus,
+}
+
+#[get("/comments", rank = 2)]
+fn comment_list(user: RequirePermission<ModerateComments>, db: DbConn, context_builder: ContextBuilder<CommentList>) -> Template {
+    comment_list_filtered(CommentFilter::default(), user, db, context_builder)
+}
+
+#[get("/comments?<filter>")]
+fn comment_list_filtered(filter: CommentFilter, _user: RequirePermission<ModerateComments>, db: DbConn, mut context_builder: ContextBuilder<CommentList>) -> Template {
+    prepare_context_builder(Some("/admin/comments"), &mut context_builder);
+
+    let status = filter.status();
+    let comments = comments::with_status(&db, status).unwrap_or_else(|err| {
+        // TODO add real logging here
+        println!("Error loading comments: {:?}", err);
+        Vec::new()
+    });
+    let context = context_builder.finalize_with_data(CommentList { comments, status });
+
+    Template::render("backend/comments/index", &context)
+}
+
+/// Approves the comment. Notifications are only sent when a pending comment is approved, so
+/// nobody gets a mail about spam.
+#[post("/comments/<id>/approve", data = "<_csrf>")]
+fn approve_comment(id: i32, _user: RequirePermission<ModerateComments>, db: DbConn, config: State<Config>, meta: State<Metadata>, _csrf: CsrfForm<NoFields>) -> Result<Flash<Redirect>, Failure> {
+    let comment = comments::get_with_id(&db, id).map_err(|_| Failure(Status::NotFound))?;
+
+    match comments::set_status(&db, id, CommentStatus::Approved) {
+        Ok(0) | Err(_) => Err(Failure(Status::NotFound)),
+        Ok(_) => {
+            if comment.status == CommentStatus::Pending.as_str() {
+                notifications::comment_approved(&config.notifications, meta.url(), &db, &comment);
+            }
+            Ok(Flash::success(Redirect::to("/admin/comments"), "Comment approved."))
+        },
+    }
+}
+
+#[post("/comments/<id>/spam", data = "<_csrf>")]
+fn mark_comment_as_spam(id: i32, _user: RequirePermission<ModerateComments>, db: DbConn, _csrf: CsrfForm<NoFields>) -> Result<Flash<Redirect>, Failure> {
+    match comments::set_status(&db, id, CommentStatus::Spam) {
+        Ok(0) | Err(_) => Err(Failure(Status::NotFound)),
+        Ok(_) => Ok(Flash::success(Redirect::to("/admin/comments"), "Comment marked as spam.")),
+    }
+}
+
+#[post("/comments/<id>/delete", data = "<_csrf>")]
+fn delete_comment(id: i32, _user: RequirePermission<ModerateComments>, db: DbConn, _csrf: CsrfForm<NoFields>) -> Result<Flash<Redirect>, Failure> {
+    match comments::delete(&db, id) {
+        Ok(0) | Err(_) => Err(Failure(Status::NotFound)),
+        Ok(_) => Ok(Flash::success(Redirect::to("/admin/comments"), "Comment deleted.")),
+    }
+}
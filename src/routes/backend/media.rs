@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use rocket_contrib::Template;
+use rocket::{Route, State};
+use rocket::response::{Redirect, Failure, Flash};
+use rocket::http::Status;
+
+use auth::permissions::{RequirePermission, UploadMedia};
+use config::Config;
+use context_builder::ContextBuilder;
+use context_builder::csrf::{CsrfForm, NoFields};
+use db::DbConn;
+use db::models::{Media, User};
+use controllers::media;
+use forms::media::MediaUpload;
+use routes::Urlify;
+use super::prepare_context_builder;
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        media_list,
+        upload_media,
+        delete_media,
+        media_picker
+    ]
+}
+
+/// A file of the media library as shown in the backend.
+#[derive(Debug, Serialize)]
+struct MediaItem {
+    media: Media,
+    url: String,
+    /// Inserted into the post body by the picker.
+    markdown: String,
+    can_delete: bool,
+}
+
+/// Data for the `backend/media/index` template.
+#[derive(Debug, Serialize)]
+struct MediaLibrary {
+    items: Vec<MediaItem>,
+    max_size_kb: u64,
+    errors: HashMap<String, String>,
+}
+
+/// Loads all files of the media library for the given user.
+fn media_items(db: &DbConn, user: &User) -> Vec<MediaItem> {
+    let files = media::all(db).unwrap_or_else(|err| {
+        // TODO add real logging here
+        println!("Error loading media: {:?}", err);
+        Vec::new()
+    });
+
+    files.into_iter()
+        .map(|file| MediaItem {
+            url: file.url(),
+            markdown: file.markdown(),
+            can_delete: user.can_delete(&file),
+            media: file,
+        })
+        .collect()
+}
+
+#[get("/media")]
+fn media_list(user: RequirePermission<UploadMedia>, db: DbConn, config: State<Config>, mut context_builder: ContextBuilder<MediaLibrary>) -> Template {
+    prepare_context_builder(Some("/admin/media"), &mut context_builder);
+
+    let items = media_items(&db, &user);
+    let context = context_builder.finalize_with_data(MediaLibrary {
+        items,
+        max_size_kb: config.uploads.max_size / 1024,
+        errors: HashMap::new(),
+    });
+
+    Template::render("backend/media/index", &context)
+}
+
+#[post("/media", data = "<upload>")]
+fn upload_media(user: RequirePermission<UploadMedia>, db: DbConn, config: State<Config>, upload: MediaUpload, mut context_builder: ContextBuilder<MediaLibrary>) -> Result<Flash<Redirect>, Template> {
+    match media::try_insert(&db, &config.uploads, &upload, &user) {
+        Ok(_) => Ok(Flash::success(Redirect::to("/admin/media"), "File uploaded successfully.")),
+        Err(errors) => {
+            prepare_context_builder(Some("/admin/media"), &mut context_builder);
+
+            let items = media_items(&db, &user);
+            let context = context_builder.finalize_with_data(MediaLibrary {
+                items,
+                max_size_kb: config.uploads.max_size / 1024,
+                errors,
+            });
+            Err(Template::render("backend/media/index", &context))
+        }
+    }
+}
+
+#[post("/media/<id>/delete", data = "<_csrf>")]
+fn delete_media(id: i32, user: RequirePermission<UploadMedia>, db: DbConn, config: State<Config>, _csrf: CsrfForm<NoFields>) -> Result<Flash<Redirect>, Failure> {
+    let file = media::get_with_id(&db, id).map_err(|_| Failure(Status::NotFound))?;
+
+    if !user.can_delete(&file) {
+        return Err(Failure(Status::Forbidden));
+    }
+
+    match media::delete(&db, &config.uploads, &file) {
+        Ok(_) => Ok(Flash::success(Redirect::to("/admin/media"), "File deleted successfully.")),
+        Err(_) => Err(Failure(Status::NotFound)),
+    }
+}
+
+/// The list of files shown by the picker of the post editor.
+/// Only the list itself is rendered, the picker loads it into the editor.
+#[get("/media/picker")]
+fn media_picker(user: RequirePermission<UploadMedia>, db: DbConn, context_builder: ContextBuilder<Vec<MediaItem>>) -> Template {
+    let items = media_items(&db, &user);
+    let context = context_builder.finalize_with_data(items);
+
+    Template::render("backend/media/picker", &context)
+}
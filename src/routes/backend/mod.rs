@@ -0,0 +1,35 @@
+pub mod posts;
+pub mod pages;
+pub mod comments;
+pub mod users;
+pub mod media;
+
+use serde::Serialize;
+use rocket::Route;
+use rocket::response::Redirect;
+
+use context_builder::ContextBuilder;
+use db::models::User;
+
+pub fn routes() -> Vec<Route> {
+    let mut routes = routes![index];
+    routes.extend(posts::routes());
+    routes.extend(pages::routes());
+    routes.extend(comments::routes());
+    routes.extend(users::routes());
+    routes.extend(media::routes());
+    routes
+}
+
+pub fn prepare_context_builder<'a, T: Serialize>(current_url: Option<&'a str>, context_builder: &mut ContextBuilder<'a, T>) {
+    let menu_builder = context_builder.menu_builder("admin");
+
+    if let Some(url) = current_url {
+        menu_builder.set_active(url);
+    }
+}
+
+#[get("/")]
+fn index(_user: User) -> Redirect {
+    Redirect::to("/admin/posts")
+}
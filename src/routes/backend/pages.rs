@@ -0,0 +1,131 @@
+use rocket_contrib::Template;
+use rocket::Route;
+use rocket::response::{Redirect, Failure, Flash};
+use rocket::http::Status;
+
+use auth::permissions::{RequirePermission, ManagePages};
+use context_builder::ContextBuilder;
+use context_builder::csrf::{CsrfForm, NoFields};
+use db::DbConn;
+use db::models::Page;
+use controllers::pages::{self, NewPage};
+use forms::pages::NewPageForm;
+use super::prepare_context_builder;
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        page_list,
+        new_page_form,
+        new_page,
+        edit_page_form,
+        edit_page,
+        delete_page_form,
+        delete_page
+    ]
+}
+
+#[get("/pages")]
+fn page_list(_user: RequirePermission<ManagePages>, db: DbConn, mut context_builder: ContextBuilder<Vec<Page>>) -> Template {
+    prepare_context_builder(Some("/admin/pages"), &mut context_builder);
+
+    let pages = pages::all(&db).unwrap_or_else(|err| {
+        // TODO add real logging here
+        println!("Error loading pages: {:?}", err);
+        Vec::new()
+    });
+    let context = context_builder.finalize_with_data(pages);
+
+    Template::render("backend/pages/index", &context)
+}
+
+/// Loads the pages that can become the parent of the given page.
+fn possible_parents(db: &DbConn, page: Option<&Page>) -> Vec<Page> {
+    pages::possible_parents(db, page).unwrap_or_else(|err| {
+        // TODO add real logging here
+        println!("Error loading pages: {:?}", err);
+        Vec::new()
+    })
+}
+
+#[get("/pages/new")]
+fn new_page_form(_user: RequirePermission<ManagePages>, db: DbConn, mut context_builder: ContextBuilder<NewPageForm>) -> Template {
+    prepare_context_builder(Some("/admin/pages"), &mut context_builder);
+    let context = context_builder.finalize_with_data(
+        NewPageForm::default().with_parents(possible_parents(&db, None))
+    );
+    Template::render("backend/pages/edit", &context)
+}
+
+#[post("/pages/new", data = "<page>")]
+fn new_page(_user: RequirePermission<ManagePages>, db: DbConn, page: CsrfForm<NewPage>, mut context_builder: ContextBuilder<NewPageForm>) -> Result<Flash<Redirect>, Template> {
+    let page = page.into_inner();
+
+    match pages::try_insert(&db, &page) {
+        Ok(page) => {
+            let url = format!("/admin/pages/{}/edit", page.id);
+            Ok(Flash::success(Redirect::to(&url), "Page created successfully."))
+        },
+        Err(errors) => {
+            prepare_context_builder(Some("/admin/pages"), &mut context_builder);
+            let context = context_builder.finalize_with_data(
+                NewPageForm::with_errors(page, errors).with_parents(possible_parents(&db, None))
+            );
+            Err(Template::render("backend/pages/edit", &context))
+        }
+    }
+}
+
+#[get("/pages/<id>/edit")]
+fn edit_page_form(id: i32, _user: RequirePermission<ManagePages>, db: DbConn, mut context_builder: ContextBuilder<NewPageForm>) -> Result<Template, Failure> {
+    let page = pages::get_with_id(&db, id).map_err(|_| Failure(Status::NotFound))?;
+
+    prepare_context_builder(Some("/admin/pages"), &mut context_builder);
+    let context = context_builder.finalize_with_data(
+        NewPageForm::from_page(&page).with_parents(possible_parents(&db, Some(&page)))
+    );
+
+    Ok(Template::render("backend/pages/edit", &context))
+}
+
+#[post("/pages/<id>/edit", data = "<changes>")]
+fn edit_page(id: i32, _user: RequirePermission<ManagePages>, db: DbConn, changes: CsrfForm<NewPage>, mut context_builder: ContextBuilder<NewPageForm>) -> Result<Result<Flash<Redirect>, Template>, Failure> {
+    let page = pages::get_with_id(&db, id).map_err(|_| Failure(Status::NotFound))?;
+    let changes = changes.into_inner();
+
+    match pages::try_update(&db, &page, &changes) {
+        Ok(page) => {
+            let url = format!("/admin/pages/{}/edit", page.id);
+            Ok(Ok(Flash::success(Redirect::to(&url), "Page saved successfully.")))
+        },
+        Err(errors) => {
+            prepare_context_builder(Some("/admin/pages"), &mut context_builder);
+            let context = context_builder.finalize_with_data(
+                NewPageForm::with_errors(changes, errors)
+                    .for_page(id)
+                    .with_parents(possible_parents(&db, Some(&page)))
+            );
+            Ok(Err(Template::render("backend/pages/edit", &context)))
+        }
+    }
+}
+
+#[get("/pages/<id>/delete")]
+fn delete_page_form(id: i32, _user: RequirePermission<ManagePages>, db: DbConn, mut context_builder: ContextBuilder<Page>) -> Result<Template, Failure> {
+    let page = pages::get_with_id(&db, id).map_err(|_| Failure(Status::NotFound))?;
+
+    prepare_context_builder(Some("/admin/pages"), &mut context_builder);
+    let context = context_builder.finalize_with_data(page);
+
+    Ok(Template::render("backend/pages/delete", &context))
+}
+
+#[post("/pages/<id>/delete", data = "<_csrf>")]
+fn delete_page(id: i32, _user: RequirePermission<ManagePages>, db: DbConn, _csrf: CsrfForm<NoFields>) -> Result<Flash<Redirect>, Failure> {
+    let page = pages::get_with_id(&db, id).map_err(|_| Failure(Status::NotFound))?;
+
+    match pages::delete(&db, page.id) {
+        Err(_) => Err(Failure(Status::NotFound)),
+        Ok(0) => Ok(Flash::error(Redirect::to("/admin/pages"), "Pages with sub pages can't be deleted.")),
+        Ok(_) => Ok(Flash::success(Redirect::to("/admin/pages"), "Page deleted successfully.")),
+    }
+}
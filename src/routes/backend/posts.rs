@@ -0,0 +1,147 @@
+use rocket_contrib::Template;
+use rocket::{Route, State};
+use rocket::response::{Redirect, Failure, Flash};
+use rocket::http::Status;
+
+use auth::permissions::{Permission, RequirePermission, EditPost, PublishPost, ManageSettings};
+use context_builder::ContextBuilder;
+use context_builder::csrf::{CsrfForm, NoFields};
+use db::DbConn;
+use db::models::{Post, User};
+use controllers::posts::{self, NewPost};
+use controllers::{tags, categories};
+use forms::posts::{NewPostForm, PostFilter};
+use markdown::Renderer;
+use super::prepare_context_builder;
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        post_list,
+        post_list_filtered,
+        edit_post_form,
+        edit_post,
+        delete_post_form,
+        delete_post,
+        publish_post,
+        unpublish_post,
+        rerender_posts
+    ]
+}
+
+/// Data for the `backend/posts/index` template.
+#[derive(Debug, Serialize)]
+struct PostList {
+    posts: Vec<Post>,
+    filter: PostFilter,
+    /// Whether the user may render all posts again.
+    can_rerender: bool,
+}
+
+/// Loads the post with the given id and checks that the user may edit it.
+fn editable_post(db: &DbConn, id: i32, user: &User) -> Result<Post, Failure> {
+    let post = posts::get_any_with_id(db, id).map_err(|_| Failure(Status::NotFound))?;
+
+    if user.can_edit(&post) {
+        Ok(post)
+    } else {
+        Err(Failure(Status::Forbidden))
+    }
+}
+
+#[get("/posts", rank = 2)]
+fn post_list(user: RequirePermission<EditPost>, db: DbConn, context_builder: ContextBuilder<PostList>) -> Template {
+    post_list_filtered(PostFilter::default(), user, db, context_builder)
+}
+
+#[get("/posts?<filter>")]
+fn post_list_filtered(filter: PostFilter, user: RequirePermission<EditPost>, db: DbConn, mut context_builder: ContextBuilder<PostList>) -> Template {
+    prepare_context_builder(Some("/admin/posts"), &mut context_builder);
+
+    let posts = posts::all(&db, &filter, &user).unwrap_or_else(|err| {
+        // TODO add real logging here
+        println!("Error loading posts: {:?}", err);
+        Vec::new()
+    });
+    let can_rerender = ManageSettings::is_granted(user.role());
+    let context = context_builder.finalize_with_data(PostList { posts, filter, can_rerender });
+
+    Template::render("backend/posts/index", &context)
+}
+
+#[get("/posts/<id>/edit")]
+fn edit_post_form(id: i32, user: RequirePermission<EditPost>, db: DbConn, mut context_builder: ContextBuilder<NewPostForm>) -> Result<Template, Failure> {
+    let post = editable_post(&db, id, &user)?;
+    let tags = tags::for_post(&db, post.id).unwrap_or_default();
+    let categories = categories::for_post(&db, post.id).unwrap_or_default();
+
+    prepare_context_builder(Some("/admin/posts"), &mut context_builder);
+    let context = context_builder.finalize_with_data(NewPostForm::from_post(&post, &tags, &categories));
+
+    Ok(Template::render("backend/posts/edit", &context))
+}
+
+#[post("/posts/<id>/edit", data = "<changes>")]
+fn edit_post(id: i32, user: RequirePermission<EditPost>, db: DbConn, renderer: State<Renderer>, changes: CsrfForm<NewPost>, mut context_builder: ContextBuilder<NewPostForm>) -> Result<Result<Flash<Redirect>, Template>, Failure> {
+    let post = editable_post(&db, id, &user)?;
+    let changes = changes.into_inner();
+
+    match posts::try_update(&db, &renderer, &post, &changes, &user) {
+        Ok(post) => {
+            let url = format!("/admin/posts/{}/edit", post.id);
+            Ok(Ok(Flash::success(Redirect::to(&url), "Post saved successfully.")))
+        },
+        Err(errors) => {
+            prepare_context_builder(Some("/admin/posts"), &mut context_builder);
+            let context = context_builder.finalize_with_data(
+                NewPostForm::with_errors(changes, errors).for_post(id)
+            );
+            Ok(Err(Template::render("backend/posts/edit", &context)))
+        }
+    }
+}
+
+#[get("/posts/<id>/delete")]
+fn delete_post_form(id: i32, user: RequirePermission<EditPost>, db: DbConn, mut context_builder: ContextBuilder<Post>) -> Result<Template, Failure> {
+    let post = editable_post(&db, id, &user)?;
+
+    prepare_context_builder(Some("/admin/posts"), &mut context_builder);
+    let context = context_builder.finalize_with_data(post);
+
+    Ok(Template::render("backend/posts/delete", &context))
+}
+
+#[post("/posts/<id>/delete", data = "<_csrf>")]
+fn delete_post(id: i32, user: RequirePermission<EditPost>, db: DbConn, _csrf: CsrfForm<NoFields>) -> Result<Flash<Redirect>, Failure> {
+    editable_post(&db, id, &user)?;
+
+    match posts::delete(&db, id) {
+        Ok(0) | Err(_) => Err(Failure(Status::NotFound)),
+        Ok(_) => Ok(Flash::success(Redirect::to("/admin/posts"), "Post deleted successfully.")),
+    }
+}
+
+#[post("/posts/<id>/publish", data = "<_csrf>")]
+fn publish_post(id: i32, _user: RequirePermission<PublishPost>, db: DbConn, _csrf: CsrfForm<NoFields>) -> Result<Flash<Redirect>, Failure> {
+    posts::publish(&db, id)
+        .map(|_| Flash::success(Redirect::to("/admin/posts"), "Post published successfully."))
+        .map_err(|_| Failure(Status::NotFound))
+}
+
+#[post("/posts/<id>/unpublish", data = "<_csrf>")]
+fn unpublish_post(id: i32, _user: RequirePermission<PublishPost>, db: DbConn, _csrf: CsrfForm<NoFields>) -> Result<Flash<Redirect>, Failure> {
+    posts::unpublish(&db, id)
+        .map(|_| Flash::success(Redirect::to("/admin/posts"), "Post unpublished successfully."))
+        .map_err(|_| Failure(Status::NotFound))
+}
+
+/// Renders all posts again, needed after the markdown or sanitizer options were changed.
+#[post("/posts/rerender", data = "<_csrf>")]
+fn rerender_posts(_user: RequirePermission<ManageSettings>, db: DbConn, renderer: State<Renderer>, _csrf: CsrfForm<NoFields>) -> Result<Flash<Redirect>, Failure> {
+    posts::rerender_all(&db, &renderer)
+        .map(|count| Flash::success(Redirect::to("/admin/posts"), format!("Rendered {} posts.", count)))
+        .map_err(|err| {
+            // TODO add real logging here
+            println!("Error rendering posts: {:?}", err);
+            Failure(Status::InternalServerError)
+        })
+}
@@ -0,0 +1,69 @@
+use rocket_contrib::Template;
+use rocket::Route;
+use rocket::response::{Redirect, Failure, Flash};
+use rocket::http::Status;
+
+use auth::permissions::{RequirePermission, ManageUsers};
+use context_builder::ContextBuilder;
+use context_builder::csrf::CsrfForm;
+use db::DbConn;
+use db::models::User;
+use controllers::users::{self, NewUser, ChangeRole};
+use forms::users::NewUserForm;
+use super::prepare_context_builder;
+
+pub fn routes() -> Vec<Route> {
+    routes![user_list, new_user_form, new_user, change_role]
+}
+
+#[get("/users")]
+fn user_list(_user: RequirePermission<ManageUsers>, db: DbConn, mut context_builder: ContextBuilder<Vec<User>>) -> Template {
+    prepare_context_builder(Some("/admin/users"), &mut context_builder);
+
+    let users = users::all(&db).unwrap_or_else(|err| {
+        // TODO add real logging here
+        println!("Error loading users: {:?}", err);
+        Vec::new()
+    });
+    let context = context_builder.finalize_with_data(users);
+
+    Template::render("backend/users/index", &context)
+}
+
+#[get("/users/new")]
+fn new_user_form(_user: RequirePermission<ManageUsers>, mut context_builder: ContextBuilder<NewUserForm>) -> Template {
+    prepare_context_builder(Some("/admin/users"), &mut context_builder);
+    let context = context_builder.finalize_with_default();
+    Template::render("backend/users/new", &context)
+}
+
+#[post("/users/new", data = "<user>")]
+fn new_user(_user: RequirePermission<ManageUsers>, db: DbConn, user: CsrfForm<NewUser>, mut context_builder: ContextBuilder<NewUserForm>) -> Result<Flash<Redirect>, Template> {
+    let user = user.into_inner();
+
+    match users::try_create(&db, &user) {
+        Ok(_) => {
+            Ok(Flash::success(Redirect::to("/admin/users"), "User created successfully."))
+        },
+        Err(errors) => {
+            prepare_context_builder(Some("/admin/users"), &mut context_builder);
+            let context = context_builder.finalize_with_data(
+                NewUserForm::with_errors(user, errors)
+            );
+            Err(Template::render("backend/users/new", &context))
+        }
+    }
+}
+
+#[post("/users/<id>/role", data = "<change>")]
+fn change_role(id: i32, user: RequirePermission<ManageUsers>, db: DbConn, change: CsrfForm<ChangeRole>) -> Result<Flash<Redirect>, Failure> {
+    let role = change.into_inner().role.map_err(|_| Failure(Status::BadRequest))?;
+
+    if id == user.id {
+        return Ok(Flash::error(Redirect::to("/admin/users"), "You can't change your own role."));
+    }
+
+    users::update_role(&db, id, role)
+        .map(|_| Flash::success(Redirect::to("/admin/users"), "Role changed successfully."))
+        .map_err(|_| Failure(Status::NotFound))
+}
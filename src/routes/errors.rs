@@ -1,21 +1,57 @@
 use rocket::{self, Catcher};
 use rocket::Request;
-use rocket::response::status;
+use rocket::http::Status;
+use rocket::response::{status, Flash, Redirect};
 use rocket_contrib::Template;
 
 use context_builder::ContextBuilder;
+use db::DbConn;
 use routes::frontend;
 
-#[error(404)]
-fn not_found(req: &Request) -> status::NotFound<Template> {
+/// Prepares the context of an error page like any other frontend page.
+/// Without a database connection, e.g. if the pool is exhausted, the page is shown without the
+/// dynamic sidebar items.
+fn prepare_context_builder<'a>(req: &'a Request) -> ContextBuilder<'a, ()> {
     let mut context_builder = req.guard::<ContextBuilder<()>>().unwrap();
-    frontend::prepare_context_builder(None, &mut context_builder);
 
+    if let Some(db) = req.guard::<DbConn>().succeeded() {
+        frontend::prepare_context_builder(None, &db, &mut context_builder);
+    }
+
+    context_builder
+}
+
+#[error(404)]
+fn not_found(req: &Request) -> status::NotFound<Template> {
+    let context_builder = prepare_context_builder(req);
     let context = context_builder.finalize_with_default();
 
     status::NotFound(Template::render("frontend/404", &context))
 }
 
+#[error(403)]
+fn forbidden(req: &Request) -> status::Custom<Template> {
+    let context_builder = prepare_context_builder(req);
+    let context = context_builder.finalize_with_default();
+
+    status::Custom(Status::Forbidden, Template::render("frontend/403", &context))
+}
+
+/// Shown when a form was submitted without a valid CSRF token.
+#[error(400)]
+fn bad_request(req: &Request) -> status::Custom<Template> {
+    let context_builder = prepare_context_builder(req);
+    let context = context_builder.finalize_with_default();
+
+    status::Custom(Status::BadRequest, Template::render("frontend/400", &context))
+}
+
+/// Sends everyone who isn't logged in to the login page.
+#[error(401)]
+fn unauthorized() -> Flash<Redirect> {
+    Flash::warning(Redirect::to("/login"), "Please log in to continue.")
+}
+
 pub fn errors() -> Vec<Catcher> {
-    errors![not_found]
+    errors![not_found, forbidden, bad_request, unauthorized]
 }
@@ -0,0 +1,153 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rocket::{Route, State};
+use rocket::response::Failure;
+use rocket::http::Status;
+use rocket_contrib::Template;
+
+use context_builder::Metadata;
+use db::DbConn;
+use db::models::Post;
+use controllers::{posts, tags};
+use routes::Urlify;
+
+/// Number of posts included in a feed.
+const FEED_SIZE: i64 = 20;
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        rss,
+        atom,
+        tag_rss,
+        tag_atom
+    ]
+}
+
+/// The supported syndication formats.
+#[derive(Debug, Clone, Copy)]
+enum FeedFormat {
+    Rss,
+    Atom,
+}
+
+impl FeedFormat {
+    fn template(&self) -> &'static str {
+        match *self {
+            FeedFormat::Rss => "feeds/rss",
+            FeedFormat::Atom => "feeds/atom",
+        }
+    }
+
+    fn file_name(&self) -> &'static str {
+        match *self {
+            FeedFormat::Rss => "feed.xml",
+            FeedFormat::Atom => "atom.xml",
+        }
+    }
+
+    /// Formats a date the way the format requires it (RFC 2822 for RSS, RFC 3339 for Atom).
+    fn format_date(&self, date: NaiveDateTime) -> String {
+        let date = DateTime::<Utc>::from_utc(date, Utc);
+
+        match *self {
+            FeedFormat::Rss => date.to_rfc2822(),
+            FeedFormat::Atom => date.to_rfc3339(),
+        }
+    }
+}
+
+/// Data for the `feeds/rss` and `feeds/atom` templates.
+/// All urls are absolute.
+#[derive(Debug, Serialize)]
+struct Feed {
+    title: String,
+    description: String,
+    /// The html page this feed belongs to.
+    link: String,
+    /// The feed itself.
+    feed_url: String,
+    updated: String,
+    items: Vec<FeedItem>,
+}
+
+impl Feed {
+    fn new(meta: &Metadata, format: FeedFormat, title: String, path: &str, posts: Vec<Post>) -> Feed {
+        let base_url = meta.url().trim_right_matches('/');
+        let updated = posts.iter()
+            .filter_map(|post| post.published_at)
+            .max()
+            .unwrap_or_else(|| Utc::now().naive_utc());
+        let feed_path = format!("{}/{}", path.trim_right_matches('/'), format.file_name());
+
+        Feed {
+            title,
+            description: meta.description().to_string(),
+            link: format!("{}{}", base_url, path),
+            feed_url: format!("{}{}", base_url, feed_path),
+            updated: format.format_date(updated),
+            items: posts.iter().map(|post| FeedItem::new(base_url, format, post)).collect(),
+        }
+    }
+}
+
+/// A single post in a [`Feed`].
+///
+/// [`Feed`]: struct.Feed.html
+#[derive(Debug, Serialize)]
+struct FeedItem {
+    title: String,
+    url: String,
+    /// A url that stays the same even if the slug of the post changes.
+    id: String,
+    author: String,
+    published: String,
+    body: String,
+}
+
+impl FeedItem {
+    fn new(base_url: &str, format: FeedFormat, post: &Post) -> FeedItem {
+        FeedItem {
+            title: post.title.clone(),
+            url: format!("{}{}", base_url, post.url()),
+            id: format!("{}{}", base_url, post.short_url()),
+            author: post.author.clone(),
+            published: format.format_date(post.published_at.unwrap_or_else(|| Utc::now().naive_utc())),
+            body: post.body_html.clone(),
+        }
+    }
+}
+
+fn site_feed(db: DbConn, meta: State<Metadata>, format: FeedFormat) -> Result<Template, Failure> {
+    let posts = posts::recent(&db, FEED_SIZE).map_err(|_| Failure(Status::InternalServerError))?;
+    let feed = Feed::new(&meta, format, meta.title().to_string(), "/", posts);
+
+    Ok(Template::render(format.template(), &feed))
+}
+
+fn tag_feed(slug: String, db: DbConn, meta: State<Metadata>, format: FeedFormat) -> Result<Template, Failure> {
+    let tag = tags::get_with_slug(&db, &slug).map_err(|_| Failure(Status::NotFound))?;
+    let posts = posts::recent_for_tag(&db, &tag, FEED_SIZE).map_err(|_| Failure(Status::InternalServerError))?;
+    let title = format!("{} - Tag: {}", meta.title(), tag.name);
+    let feed = Feed::new(&meta, format, title, &tag.url(), posts);
+
+    Ok(Template::render(format.template(), &feed))
+}
+
+#[get("/feed.xml")]
+fn rss(db: DbConn, meta: State<Metadata>) -> Result<Template, Failure> {
+    site_feed(db, meta, FeedFormat::Rss)
+}
+
+#[get("/atom.xml")]
+fn atom(db: DbConn, meta: State<Metadata>) -> Result<Template, Failure> {
+    site_feed(db, meta, FeedFormat::Atom)
+}
+
+#[get("/tag/<slug>/feed.xml")]
+fn tag_rss(slug: String, db: DbConn, meta: State<Metadata>) -> Result<Template, Failure> {
+    tag_feed(slug, db, meta, FeedFormat::Rss)
+}
+
+#[get("/tag/<slug>/atom.xml")]
+fn tag_atom(slug: String, db: DbConn, meta: State<Metadata>) -> Result<Template, Failure> {
+    tag_feed(slug, db, meta, FeedFormat::Atom)
+}
@@ -1,66 +1,166 @@
 use std::path::{Path, PathBuf};
 //use std::collections::HashMap;
 
+use chrono::NaiveDate;
 use serde::Serialize;
 use rocket_contrib::Template;
 use rocket::response::NamedFile;
-use rocket::Route;
+use rocket::{Route, State};
 use rocket::response::{Redirect, Failure};
 use rocket::http::Status;
-use rocket::request::Form;
 use rocket::response::Flash;
 
 use context_builder::ContextBuilder;
+use context_builder::nav::Link;
 use db::DbConn;
-use db::models::Post;
+use db::models::{Post, PostStatus, Tag, Category, Page};
+use auth::permissions::{RequirePermission, CreatePost};
 use controllers::posts::{self, NewPost};
+use controllers::pagination::{Paginated, Pagination};
+use controllers::{tags, categories, comments, pages, search};
+use controllers::search::SearchResult;
+use controllers::comments::{NewComment, ThreadedComment};
 use forms::posts::NewPostForm;
+use forms::comments::CommentForm;
+use forms::search::SearchQuery;
+use markdown::Renderer;
+use config::Config;
+use spam::{SpamChecked, SpamToken};
 //use response::ResponseResult;
 use routes::Urlify;
+use sidebar;
 
 pub fn routes() -> Vec<Route> {
-    routes![index, static_files, new_post_form, new_post, get_post_short, get_post_long, test_flash]
+    routes![
+        index,
+        index_page,
+        static_files,
+        new_post_form,
+        new_post,
+        get_post_short,
+        get_post_long,
+        new_comment,
+        tag,
+        tag_page,
+        category,
+        category_page,
+        archive_year,
+        archive_year_page,
+        archive_month,
+        archive_month_page,
+        search_form,
+        search_results,
+        page,
+        media_files,
+        test_flash
+    ]
 }
 
-pub fn prepare_context_builder<'a, T: Serialize>(current_url: Option<&'a str>, context_builder: &mut ContextBuilder<'a, T>) {
-    let menu_builder = context_builder.menu_builder("main");
-
+/// Sets the active menu item and loads the dynamic sidebar items with the given connection.
+/// Every frontend page has to call this.
+pub fn prepare_context_builder<'a, T: Serialize>(current_url: Option<&'a str>, db: &DbConn, context_builder: &mut ContextBuilder<'a, T>) {
     if let Some(url) = current_url {
-        menu_builder.set_active(url);
+        context_builder.menu_builder("main").set_active(url);
+    }
+
+    sidebar::load(db, context_builder);
+}
+
+/// Data for the `frontend/archive` template.
+/// A page of posts with a title describing which posts are listed.
+#[derive(Debug, Serialize)]
+struct Archive {
+    title: String,
+    items: Vec<Post>,
+    pagination: Pagination,
+}
+
+impl Archive {
+    fn new<T: ToString>(title: T, posts: Paginated<Post>) -> Archive {
+        Archive {
+            title: title.to_string(),
+            items: posts.items,
+            pagination: posts.pagination,
+        }
     }
 }
 
+/// Data for the `frontend/post` template.
+#[derive(Debug, Serialize)]
+struct PostPage {
+    post: Post,
+    tags: Vec<Tag>,
+    categories: Vec<Category>,
+    comments: Vec<ThreadedComment>,
+    comment_form: CommentForm,
+}
+
+impl PostPage {
+    /// Loads everything shown on the page of the given post.
+    fn load(db: &DbConn, post: Post, comment_form: CommentForm) -> PostPage {
+        PostPage {
+            tags: tags::for_post(db, post.id).unwrap_or_default(),
+            categories: categories::for_post(db, post.id).unwrap_or_default(),
+            comments: comments::threaded_for_post(db, post.id).unwrap_or_default(),
+            comment_form,
+            post,
+        }
+    }
+}
+
+/// Data for the `frontend/search` template.
+#[derive(Debug, Serialize)]
+struct SearchPage {
+    terms: String,
+    results: Vec<SearchResult>,
+}
+
 #[get("/")]
-fn index(db: DbConn, mut context_builder: ContextBuilder<Vec<Post>>) -> Template {
-    prepare_context_builder(Some("/"), &mut context_builder);
+fn index(db: DbConn, context_builder: ContextBuilder<Paginated<Post>>) -> Result<Template, Failure> {
+    index_page(1, db, context_builder)
+}
 
-    let posts = posts::posts(&db).into_iter().map(|mut post| {
-        post.body = parse_markdown(&post.body);
-        post
-    }).collect();
+#[get("/page/<page>")]
+fn index_page(page: i64, db: DbConn, mut context_builder: ContextBuilder<Paginated<Post>>) -> Result<Template, Failure> {
+    let posts = posts::paginated(&db, page).map_err(|_| Failure(Status::NotFound))?;
 
+    prepare_context_builder(Some("/"), &db, &mut context_builder);
     let context = context_builder.finalize_with_data(posts);
 
-    Template::render("frontend/index", &context)
+    Ok(Template::render("frontend/index", &context))
 }
 
 #[get("/post")]
-fn new_post_form(mut context_builder: ContextBuilder<NewPostForm>) -> Template {
-    prepare_context_builder(Some("/post/new"), &mut context_builder);
+fn new_post_form(_user: RequirePermission<CreatePost>, db: DbConn, spam_token: SpamToken, mut context_builder: ContextBuilder<NewPostForm>) -> Template {
+    prepare_context_builder(Some("/post/new"), &db, &mut context_builder);
+    spam_token.add_to(&mut context_builder);
     let context = context_builder.finalize_with_default();
     Template::render("frontend/create", &context)
 }
 
 #[post("/post", data = "<post>")]
-fn new_post<'a>(db: DbConn, post: Form<'a, NewPost>, mut context_builder: ContextBuilder<NewPostForm>) -> Result<Flash<Redirect>, Template> {
-    let post = post.into_inner();
+fn new_post(user: RequirePermission<CreatePost>, db: DbConn, renderer: State<Renderer>, post: SpamChecked<NewPost>, spam_token: SpamToken, mut context_builder: ContextBuilder<NewPostForm>) -> Result<Flash<Redirect>, Template> {
+    let (post, verdict) = post.into_parts();
 
-    match posts::try_insert(&db, &post) {
+    match verdict.and_then(|_| posts::try_insert(&db, &renderer, &post, &user)) {
         Ok(post) => {
-            Ok(Flash::success(Redirect::to(&post.url()), "Post created successfully."))
+            match post.status() {
+                PostStatus::Published => {
+                    Ok(Flash::success(Redirect::to(&post.url()), "Post created successfully."))
+                },
+                PostStatus::Scheduled => {
+                    let url = format!("/admin/posts/{}/edit", post.id);
+                    Ok(Flash::success(Redirect::to(&url), "Post scheduled successfully."))
+                },
+                _ => {
+                    let url = format!("/admin/posts/{}/edit", post.id);
+                    Ok(Flash::success(Redirect::to(&url), "Post saved successfully."))
+                },
+            }
         },
         Err(errors) => {
-            prepare_context_builder(Some("/post/new"), &mut context_builder);
+            prepare_context_builder(Some("/post/new"), &db, &mut context_builder);
+            spam_token.add_to(&mut context_builder);
             let context = context_builder.finalize_with_data(
                 NewPostForm::with_errors(post, errors)
             );
@@ -85,12 +185,17 @@ fn get_post_short(id: i32, db: DbConn) -> Result<Redirect, Failure> {
 }
 
 #[get("/post/<slug>", rank = 2)]
-fn get_post_long(slug: String, db: DbConn, mut context_builder: ContextBuilder<Post>) -> Result<Template, Failure> {
+fn get_post_long(slug: String, db: DbConn, spam_token: SpamToken, mut context_builder: ContextBuilder<PostPage>) -> Result<Template, Failure> {
     match posts::get_with_slug(&db, slug) {
-        Ok(mut post) => {
-            post.body = parse_markdown(&post.body);
-            prepare_context_builder(Some("/post"), &mut context_builder);
-            let context = context_builder.finalize_with_data(post);
+        Ok(post) => {
+            if let Err(err) = posts::add_view(&db, post.id) {
+                // TODO add real logging here
+                println!("Error counting view: {:?}", err);
+            }
+
+            prepare_context_builder(Some("/post"), &db, &mut context_builder);
+            spam_token.add_to(&mut context_builder);
+            let context = context_builder.finalize_with_data(PostPage::load(&db, post, CommentForm::default()));
             Ok(Template::render("frontend/post", &context))
         },
         Err(_) => {
@@ -99,31 +204,165 @@ fn get_post_long(slug: String, db: DbConn, mut context_builder: ContextBuilder<P
     }
 }
 
+#[post("/post/<id>/comments", data = "<comment>")]
+fn new_comment(id: i32, db: DbConn, comment: SpamChecked<NewComment>, spam_token: SpamToken, mut context_builder: ContextBuilder<PostPage>) -> Result<Result<Flash<Redirect>, Template>, Failure> {
+    let post = posts::get_with_id(&db, id).map_err(|_| Failure(Status::NotFound))?;
+    let (comment, verdict) = comment.into_parts();
+
+    match verdict.and_then(|_| comments::try_insert(&db, post.id, &comment)) {
+        Ok(_) => {
+            Ok(Ok(Flash::success(Redirect::to(&post.url()), "Thanks for your comment! It will show up once it is approved.")))
+        },
+        Err(errors) => {
+            prepare_context_builder(Some("/post"), &db, &mut context_builder);
+            spam_token.add_to(&mut context_builder);
+            let context = context_builder.finalize_with_data(
+                PostPage::load(&db, post, CommentForm::with_errors(comment, errors))
+            );
+            Ok(Err(Template::render("frontend/post", &context)))
+        }
+    }
+}
+
+#[get("/tag/<slug>")]
+fn tag(slug: String, db: DbConn, context_builder: ContextBuilder<Archive>) -> Result<Template, Failure> {
+    tag_page(slug, 1, db, context_builder)
+}
+
+#[get("/tag/<slug>/page/<page>")]
+fn tag_page(slug: String, page: i64, db: DbConn, mut context_builder: ContextBuilder<Archive>) -> Result<Template, Failure> {
+    let tag = tags::get_with_slug(&db, &slug).map_err(|_| Failure(Status::NotFound))?;
+    let posts = posts::paginated_for_tag(&db, &tag, page).map_err(|_| Failure(Status::NotFound))?;
+
+    prepare_context_builder(None, &db, &mut context_builder);
+    let context = context_builder.finalize_with_data(Archive::new(format!("Tag: {}", tag.name), posts));
+
+    Ok(Template::render("frontend/archive", &context))
+}
+
+#[get("/category/<slug>")]
+fn category(slug: String, db: DbConn, context_builder: ContextBuilder<Archive>) -> Result<Template, Failure> {
+    category_page(slug, 1, db, context_builder)
+}
+
+#[get("/category/<slug>/page/<page>")]
+fn category_page(slug: String, page: i64, db: DbConn, mut context_builder: ContextBuilder<Archive>) -> Result<Template, Failure> {
+    let category = categories::get_with_slug(&db, &slug).map_err(|_| Failure(Status::NotFound))?;
+    let posts = posts::paginated_for_category(&db, &category, page).map_err(|_| Failure(Status::NotFound))?;
+
+    prepare_context_builder(None, &db, &mut context_builder);
+    let context = context_builder.finalize_with_data(Archive::new(format!("Category: {}", category.name), posts));
+
+    Ok(Template::render("frontend/archive", &context))
+}
+
+#[get("/archive/<year>")]
+fn archive_year(year: i32, db: DbConn, context_builder: ContextBuilder<Archive>) -> Result<Template, Failure> {
+    archive_year_page(year, 1, db, context_builder)
+}
+
+#[get("/archive/<year>/page/<page>")]
+fn archive_year_page(year: i32, page: i64, db: DbConn, mut context_builder: ContextBuilder<Archive>) -> Result<Template, Failure> {
+    let start = NaiveDate::from_ymd_opt(year, 1, 1).ok_or(Failure(Status::NotFound))?;
+    let end = NaiveDate::from_ymd_opt(year + 1, 1, 1).ok_or(Failure(Status::NotFound))?;
+    let base_url = format!("/archive/{}", year);
+
+    let posts = posts::paginated_for_period(&db, &base_url, start.and_hms(0, 0, 0), end.and_hms(0, 0, 0), page)
+        .map_err(|_| Failure(Status::NotFound))?;
+
+    prepare_context_builder(None, &db, &mut context_builder);
+    let context = context_builder.finalize_with_data(Archive::new(format!("Archive: {}", year), posts));
+
+    Ok(Template::render("frontend/archive", &context))
+}
+
+#[get("/archive/<year>/<month>")]
+fn archive_month(year: i32, month: u32, db: DbConn, context_builder: ContextBuilder<Archive>) -> Result<Template, Failure> {
+    archive_month_page(year, month, 1, db, context_builder)
+}
+
+#[get("/archive/<year>/<month>/page/<page>")]
+fn archive_month_page(year: i32, month: u32, page: i64, db: DbConn, mut context_builder: ContextBuilder<Archive>) -> Result<Template, Failure> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or(Failure(Status::NotFound))?;
+    let end = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }.ok_or(Failure(Status::NotFound))?;
+    let base_url = format!("/archive/{}/{:02}", year, month);
+
+    let posts = posts::paginated_for_period(&db, &base_url, start.and_hms(0, 0, 0), end.and_hms(0, 0, 0), page)
+        .map_err(|_| Failure(Status::NotFound))?;
+
+    prepare_context_builder(None, &db, &mut context_builder);
+    let title = format!("Archive: {}", start.format("%B %Y"));
+    let context = context_builder.finalize_with_data(Archive::new(title, posts));
+
+    Ok(Template::render("frontend/archive", &context))
+}
+
 #[get("/test-flash/<name>/<msg>")]
 fn test_flash(name: String, msg: String) -> Flash<Redirect> {
     Flash::new(Redirect::to("/"), name, msg)
 }
 
+#[get("/search", rank = 2)]
+fn search_form(db: DbConn, context_builder: ContextBuilder<SearchPage>) -> Template {
+    search_results(SearchQuery::default(), db, context_builder)
+}
+
+#[get("/search?<query>")]
+fn search_results(query: SearchQuery, db: DbConn, mut context_builder: ContextBuilder<SearchPage>) -> Template {
+    let terms = query.terms().to_string();
+    let results = search::search(&db, &terms).unwrap_or_else(|err| {
+        // TODO add real logging here
+        println!("Error searching for {:?}: {:?}", terms, err);
+        Vec::new()
+    });
+
+    prepare_context_builder(None, &db, &mut context_builder);
+    let context = context_builder.finalize_with_data(SearchPage { terms, results });
+
+    Template::render("frontend/search", &context)
+}
+
 // TODO add more routes
 
+/// Serves the (possibly nested) page matching the request path.
+/// The `Page` guard forwards if there is no such page, so `static_files` is tried next.
+#[get("/<_path..>", rank = 999)]
+fn page(_path: PathBuf, mut page: Page, db: DbConn, renderer: State<Renderer>, mut context_builder: ContextBuilder<Page>) -> Template {
+    page.body = renderer.render(&page.body).html;
+
+    let ancestors = pages::ancestors(&db, &page).unwrap_or_else(|err| {
+        // TODO add real logging here
+        println!("Error loading parent pages: {:?}", err);
+        Vec::new()
+    });
+
+    context_builder.add_breadcrumb(Link::new("Home", "/"));
+    for ancestor in ancestors.iter().chain(Some(&page)) {
+        context_builder.add_breadcrumb(Link::new(&ancestor.title, ancestor.url()));
+    }
+
+    prepare_context_builder(None, &db, &mut context_builder);
+    let top_level_url = ancestors.first().unwrap_or(&page).url();
+    context_builder.menu_builder("main").set_active(top_level_url);
+    let context = context_builder.finalize_with_data(page);
+
+    Template::render("frontend/page", &context)
+}
+
+/// Serves the files of the media library from the uploads directory.
+/// Like `static_files` this can't be used to request files outside of the directory.
+#[get("/media/<path..>")]
+fn media_files(path: PathBuf, config: State<Config>) -> Option<NamedFile> {
+    NamedFile::open(Path::new(&config.uploads.directory).join(path)).ok()
+}
+
 /// Serving static files in `static/` directory before 404ing.
 /// This is automatically protected from requesting files outside of the `static/` directory.
 #[get("/<path..>", rank = 1000)]
 fn static_files(path: PathBuf) -> Option<NamedFile> {
     NamedFile::open(Path::new("static/").join(path)).ok()
 }
-
-/// Parses markdown to html using pulldown_cmark.
-fn parse_markdown(md: &str) -> String {
-    use pulldown_cmark::{Parser, html, Options, OPTION_ENABLE_TABLES, OPTION_ENABLE_FOOTNOTES};
-
-    let mut options = Options::empty();
-    options.insert(OPTION_ENABLE_TABLES);
-    options.insert(OPTION_ENABLE_FOOTNOTES);
-
-    let mut output = String::new();
-    let parser = Parser::new_ext(md, options);
-    html::push_html(&mut output, parser);
-
-    output
-}
\ No newline at end of file
@@ -1,6 +1,8 @@
 pub mod frontend;
+pub mod feeds;
 pub mod backend;
 pub mod errors;
+pub mod auth;
 
 use rocket::Rocket;
 
@@ -10,10 +12,10 @@ use rocket::Rocket;
 /// [`Rocket`]: https://api.rocket.rs/rocket/struct.Rocket.html
 /// [`satellite_core::rocket`]: fn.rocket.html
 pub fn mount_to(rocket: Rocket) -> Rocket {
-    rocket.mount("/", frontend::routes()).mount(
-        "/admin",
-        backend::routes(),
-    )
+    rocket.mount("/", frontend::routes())
+        .mount("/", feeds::routes())
+        .mount("/", auth::routes())
+        .mount("/admin", backend::routes())
 }
 
 pub fn add_catchers_to(rocket: Rocket) -> Rocket {
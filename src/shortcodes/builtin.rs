@@ -0,0 +1,63 @@
+use controllers::posts;
+use routes::Urlify;
+use super::ShortcodeContext;
+
+#[derive(Debug, Serialize)]
+struct Video<'a> {
+    id: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct Gallery<'a> {
+    images: &'a [String],
+}
+
+#[derive(Debug, Serialize)]
+struct PostLink {
+    title: String,
+    url: String,
+}
+
+/// Embeds a YouTube video: `{{< youtube <video id> >}}`
+pub fn youtube(context: &ShortcodeContext, args: &[String]) -> Result<String, String> {
+    if args.len() != 1 {
+        return Err("needs exactly one argument, the id of the video".to_string());
+    }
+
+    let id = &args[0];
+    if id.is_empty() || !id.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("got an invalid video id \"{}\"", id));
+    }
+
+    context.render("youtube.html", &Video { id })
+}
+
+/// Shows a list of images: `{{< gallery <url> <url> ... >}}`
+/// The urls have to be relative or use http(s).
+pub fn gallery(context: &ShortcodeContext, args: &[String]) -> Result<String, String> {
+    if args.is_empty() {
+        return Err("needs the urls of the images as arguments".to_string());
+    }
+
+    let allowed = |url: &String| {
+        url.starts_with('/') || url.starts_with("http://") || url.starts_with("https://")
+    };
+    if let Some(url) = args.iter().find(|url| !allowed(url)) {
+        return Err(format!("got an invalid image url \"{}\"", url));
+    }
+
+    context.render("gallery.html", &Gallery { images: args })
+}
+
+/// Links to another published post by its slug: `{{< post <slug> >}}`
+pub fn post(context: &ShortcodeContext, args: &[String]) -> Result<String, String> {
+    if args.len() != 1 {
+        return Err("needs exactly one argument, the slug of the post".to_string());
+    }
+
+    let slug = &args[0];
+    let post = posts::get_with_slug(context.db, slug.clone())
+        .map_err(|_| format!("links to \"{}\", but there is no published post with that slug", slug))?;
+
+    context.render("post.html", &PostLink { title: post.title.clone(), url: post.url() })
+}
@@ -0,0 +1,485 @@
+pub mod builtin;
+
+use std::cmp;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Serialize;
+use tera::Tera;
+
+use db::DbConn;
+
+/// Directory with the templates used by shortcodes. They end in `.html` instead of `.html.tera`,
+/// so they are not loaded by rocket and get autoescaped by tera.
+const TEMPLATES: &'static str = "templates/shortcodes/*.html";
+
+/// Everything a [`Shortcode`] has access to while rendering.
+///
+/// [`Shortcode`]: trait.Shortcode.html
+pub struct ShortcodeContext<'a> {
+    pub db: &'a DbConn,
+    templates: &'a Tera,
+}
+
+impl<'a> ShortcodeContext<'a> {
+    /// Renders one of the templates in `templates/shortcodes/`, e.g. `youtube.html`.
+    pub fn render<T: Serialize>(&self, template: &str, data: &T) -> Result<String, String> {
+        self.templates.render(template, data).map_err(|err| {
+            // TODO add real logging here
+            println!("Error rendering shortcode template {}: {:?}", template, err);
+            "could not be rendered".to_string()
+        })
+    }
+}
+
+/// Renders the html for a shortcode in a post body.
+///
+/// Shortcodes are registered by name in [`Shortcodes`] and used like this:
+///
+/// ```text
+/// {{< youtube dQw4w9WgXcQ >}}
+/// {{< post "hello-world" >}}
+/// ```
+///
+/// Every `Fn(&ShortcodeContext, &[String]) -> Result<String, String>` is a shortcode. It gets
+/// the arguments and returns the html or an error message, which is shown to the author.
+///
+/// [`Shortcodes`]: struct.Shortcodes.html
+pub trait Shortcode: Send + Sync {
+    fn render(&self, context: &ShortcodeContext, args: &[String]) -> Result<String, String>;
+}
+
+impl<F> Shortcode for F
+where
+    F: Fn(&ShortcodeContext, &[String]) -> Result<String, String> + Send + Sync,
+{
+    fn render(&self, context: &ShortcodeContext, args: &[String]) -> Result<String, String> {
+        self(context, args)
+    }
+}
+
+/// Registry of all [`Shortcode`]s by name.
+///
+/// [`Shortcode`]: trait.Shortcode.html
+pub struct Shortcodes {
+    shortcodes: HashMap<String, Box<Shortcode>>,
+    templates: Tera,
+}
+
+impl Shortcodes {
+    /// Creates an empty registry, shortcodes can render the given templates.
+    pub fn new(templates: Tera) -> Shortcodes {
+        Shortcodes {
+            shortcodes: HashMap::new(),
+            templates,
+        }
+    }
+
+    /// Registers a shortcode under the given name, replacing any shortcode with the same name.
+    pub fn register<S: Shortcode + 'static>(mut self, name: &str, shortcode: S) -> Shortcodes {
+        self.shortcodes.insert(name.to_string(), Box::new(shortcode));
+        self
+    }
+
+    /// Renders all shortcodes in `text`.
+    /// Returns an error message for every shortcode that is unknown or failed to render.
+    pub fn expand(&self, db: &DbConn, text: &str) -> Result<Expanded, Vec<String>> {
+        let context = ShortcodeContext {
+            db,
+            templates: &self.templates,
+        };
+        let segments = parse(text).map_err(|err| vec![err])?;
+        let nonce = nonce().ok_or_else(|| vec!["Shortcodes could not be rendered.".to_string()])?;
+
+        let mut expanded = Expanded {
+            text: String::with_capacity(text.len()),
+            placeholders: Vec::new(),
+        };
+        let mut errors = Vec::new();
+
+        for segment in segments {
+            match segment {
+                Segment::Text(text) => expanded.text.push_str(text),
+                Segment::Shortcode(name, args) => {
+                    let html = match self.shortcodes.get(&name) {
+                        Some(shortcode) => shortcode.render(&context, &args),
+                        None => Err("is unknown".to_string()),
+                    };
+
+                    match html {
+                        Ok(html) => {
+                            let placeholder = format!("shortcode{}x{}x", nonce, expanded.placeholders.len());
+                            expanded.text.push_str(&placeholder);
+                            expanded.placeholders.push((placeholder, html));
+                        },
+                        Err(err) => errors.push(format!("Shortcode \"{}\" {}.", name, err)),
+                    }
+                },
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(expanded)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Returns all shortcodes of this crate.
+pub fn registry() -> Result<Shortcodes, String> {
+    let templates = Tera::new(TEMPLATES).map_err(|err| format!("{:?}", err))?;
+
+    Ok(Shortcodes::new(templates)
+        .register("youtube", builtin::youtube)
+        .register("gallery", builtin::gallery)
+        .register("post", builtin::post))
+}
+
+/// Text with all shortcodes replaced by placeholders.
+///
+/// The html of the shortcodes is inserted after the text went through markdown and the
+/// sanitizer, so shortcodes can use markup authors are not allowed to (like iframes).
+#[derive(Debug, Clone)]
+pub struct Expanded {
+    text: String,
+    placeholders: Vec<(String, String)>,
+}
+
+impl Expanded {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the placeholders in the rendered `html` with the html of the shortcodes.
+    /// Shortcodes on a line of their own are not wrapped in a paragraph.
+    pub fn insert_into(&self, html: &str) -> String {
+        let mut html = html.to_string();
+
+        for &(ref placeholder, ref shortcode) in &self.placeholders {
+            html = html
+                .replace(&format!("<p>{}</p>", placeholder), shortcode)
+                .replace(placeholder, shortcode);
+        }
+
+        html
+    }
+}
+
+/// Random part of the placeholders, so they can't be entered by authors.
+fn nonce() -> Option<String> {
+    let mut bytes = [0; 8];
+    SystemRandom::new().fill(&mut bytes).ok()?;
+    Some(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Segment<'a> {
+    Text(&'a str),
+    /// Name and arguments.
+    Shortcode(String, Vec<String>),
+}
+
+/// Splits the text into plain text and shortcodes.
+/// Shortcodes in code blocks and code spans are left as they are, so posts can show them.
+fn parse(text: &str) -> Result<Vec<Segment>, String> {
+    let code = code_ranges(text);
+    let mut segments = Vec::new();
+    // start of the current text segment and where to look for the next shortcode
+    let mut text_start = 0;
+    let mut position = 0;
+
+    while let Some(found) = text[position..].find("{{<") {
+        let start = position + found;
+        if let Some(range) = code.iter().find(|range| range.start <= start && start < range.end) {
+            position = range.end;
+            continue;
+        }
+
+        if start > text_start {
+            segments.push(Segment::Text(&text[text_start..start]));
+        }
+
+        let inner = &text[start + 3..];
+        let end = inner.find(">}}").ok_or_else(|| "A shortcode is missing its closing \">}}\".".to_string())?;
+        let mut words = split_args(&inner[..end])?.into_iter();
+        let name = words.next().ok_or_else(|| "A shortcode is missing its name.".to_string())?;
+
+        segments.push(Segment::Shortcode(name, words.collect()));
+        position = start + 3 + end + 3;
+        text_start = position;
+    }
+
+    if text_start < text.len() {
+        segments.push(Segment::Text(&text[text_start..]));
+    }
+
+    Ok(segments)
+}
+
+/// Returns the byte ranges of the fenced and indented code blocks and of the code spans in the
+/// markdown `text`.
+///
+/// This only follows markdown as far as needed to find code, e.g. an indented line after a list
+/// item is never taken as a code block.
+fn code_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    // fence character, fence length and start of the open fenced block
+    let mut fence: Option<(char, usize, usize)> = None;
+    // start and end of the open indented block
+    let mut indented: Option<(usize, usize)> = None;
+    let mut after_blank = true;
+    let mut in_list = false;
+    let mut offset = 0;
+
+    for line in text.split('\n') {
+        let start = offset;
+        let end = cmp::min(offset + line.len() + 1, text.len());
+        offset = end;
+        let trimmed = line.trim_left();
+
+        if let Some((c, length, block_start)) = fence {
+            if closes_fence(trimmed, c, length) {
+                blocks.push(block_start..end);
+                fence = None;
+                after_blank = true;
+            }
+            continue;
+        }
+
+        let blank = trimmed.trim().is_empty();
+        let indentation = line.len() - trimmed.len();
+        let code_line = !blank && (indentation >= 4 || line.starts_with('\t'));
+
+        if let Some((block_start, block_end)) = indented {
+            if blank || code_line {
+                indented = Some((block_start, if code_line { end } else { block_end }));
+                continue;
+            }
+            blocks.push(block_start..block_end);
+            indented = None;
+        } else if code_line && after_blank && !in_list {
+            indented = Some((start, end));
+            continue;
+        }
+
+        if let Some((c, length)) = opens_fence(trimmed) {
+            fence = Some((c, length, start));
+            continue;
+        }
+
+        if !blank && !code_line {
+            in_list = is_list_item(trimmed);
+        }
+        after_blank = blank;
+    }
+
+    if let Some((_, _, block_start)) = fence {
+        blocks.push(block_start..text.len());
+    }
+    if let Some((block_start, block_end)) = indented {
+        blocks.push(block_start..block_end);
+    }
+
+    let mut ranges = Vec::new();
+    let mut gap_start = 0;
+    for block in blocks {
+        code_spans(text, gap_start..block.start, &mut ranges);
+        gap_start = block.end;
+        ranges.push(block);
+    }
+    code_spans(text, gap_start..text.len(), &mut ranges);
+
+    ranges
+}
+
+/// Returns the character and length of the fence if the line starts a fenced code block.
+fn opens_fence(line: &str) -> Option<(char, usize)> {
+    let c = match line.chars().next() {
+        Some(c) if c == '`' || c == '~' => c,
+        _ => return None,
+    };
+    let length = line.chars().take_while(|&fence| fence == c).count();
+
+    if length < 3 || (c == '`' && line[length..].contains('`')) {
+        None
+    } else {
+        Some((c, length))
+    }
+}
+
+fn closes_fence(line: &str, c: char, length: usize) -> bool {
+    let fence = line.chars().take_while(|&fence| fence == c).count();
+    fence >= length && line[fence..].trim().is_empty()
+}
+
+fn is_list_item(line: &str) -> bool {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return true;
+    }
+
+    let digits = line.chars().take_while(|c| c.is_digit(10)).count();
+    digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+/// Adds the code spans between `range.start` and `range.end` to `ranges`.
+/// A code span ends with as many backticks as it starts with, at the latest at the end of the
+/// paragraph.
+fn code_spans(text: &str, range: Range<usize>, ranges: &mut Vec<Range<usize>>) {
+    let bytes = text.as_bytes();
+    let mut position = range.start;
+
+    while let Some(found) = text[position..range.end].find('`') {
+        let open = position + found;
+        let length = backticks(bytes, open, range.end);
+        let after = open + length;
+        let paragraph_end = text[after..range.end].find("\n\n").map_or(range.end, |end| after + end);
+
+        let mut close = after;
+        position = after;
+        while close < paragraph_end {
+            let run = backticks(bytes, close, paragraph_end);
+            if run == length {
+                ranges.push(open..close + length);
+                position = close + length;
+                break;
+            }
+            close += cmp::max(run, 1);
+        }
+    }
+}
+
+/// Returns the number of backticks at `start`.
+fn backticks(bytes: &[u8], start: usize, end: usize) -> usize {
+    bytes[start..end].iter().take_while(|&&b| b == b'`').count()
+}
+
+/// Splits the arguments at whitespace. Arguments containing whitespace can be quoted with `"`.
+fn split_args(args: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut chars = args.trim().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let mut word = String::new();
+        if c == '"' {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => word.push(c),
+                    None => return Err("A shortcode argument is missing its closing quote.".to_string()),
+                }
+            }
+        } else {
+            word.push(c);
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+        }
+
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_shortcodes() {
+        assert_eq!(
+            parse("Watch {{< youtube abc >}} and {{<post \"hello world\">}}!"),
+            Ok(vec![
+                Segment::Text("Watch "),
+                Segment::Shortcode("youtube".to_string(), args(&["abc"])),
+                Segment::Text(" and "),
+                Segment::Shortcode("post".to_string(), args(&["hello world"])),
+                Segment::Text("!"),
+            ])
+        );
+        assert_eq!(parse("{{< gallery >}}"), Ok(vec![Segment::Shortcode("gallery".to_string(), Vec::new())]));
+        assert_eq!(parse("no shortcodes"), Ok(vec![Segment::Text("no shortcodes")]));
+    }
+
+    #[test]
+    fn reject_malformed_shortcodes() {
+        assert!(parse("{{< youtube abc").is_err());
+        assert!(parse("{{< >}}").is_err());
+        assert!(parse("{{< post \"hello >}}").is_err());
+    }
+
+    #[test]
+    fn skip_shortcodes_in_code_blocks() {
+        assert_eq!(
+            parse("```\n{{< youtube abc >}}\n```\n{{< gallery >}}"),
+            Ok(vec![
+                Segment::Text("```\n{{< youtube abc >}}\n```\n"),
+                Segment::Shortcode("gallery".to_string(), Vec::new()),
+            ])
+        );
+        assert_eq!(
+            parse("~~~~ markdown\n{{< youtube\n~~~\n~~~~\n"),
+            Ok(vec![Segment::Text("~~~~ markdown\n{{< youtube\n~~~\n~~~~\n")])
+        );
+        assert_eq!(
+            parse("Like this:\n\n    {{< post hello >}}\n\nSee {{< post hello >}}"),
+            Ok(vec![
+                Segment::Text("Like this:\n\n    {{< post hello >}}\n\nSee "),
+                Segment::Shortcode("post".to_string(), args(&["hello"])),
+            ])
+        );
+        assert_eq!(
+            parse("- item\n\n    {{< gallery >}}"),
+            Ok(vec![
+                Segment::Text("- item\n\n    "),
+                Segment::Shortcode("gallery".to_string(), Vec::new()),
+            ])
+        );
+    }
+
+    #[test]
+    fn skip_shortcodes_in_code_spans() {
+        assert_eq!(
+            parse("Use `{{< post x >}}` or ``{{< gallery >}}``."),
+            Ok(vec![Segment::Text("Use `{{< post x >}}` or ``{{< gallery >}}``.")])
+        );
+        assert_eq!(
+            parse("a ` b {{< gallery >}}\n\n`"),
+            Ok(vec![
+                Segment::Text("a ` b "),
+                Segment::Shortcode("gallery".to_string(), Vec::new()),
+                Segment::Text("\n\n`"),
+            ])
+        );
+    }
+
+    #[test]
+    fn insert_shortcode_html() {
+        let expanded = Expanded {
+            text: String::new(),
+            placeholders: vec![
+                ("shortcode0".to_string(), "<div>a</div>".to_string()),
+                ("shortcode1".to_string(), "<a>b</a>".to_string()),
+            ],
+        };
+
+        assert_eq!(
+            expanded.insert_into("<p>shortcode0</p>\n<p>see shortcode1</p>"),
+            "<div>a</div>\n<p>see <a>b</a></p>"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use serde::Serialize;
+
+use context_builder::ContextBuilder;
+use context_builder::meta::SidebarItem;
+use context_builder::nav::Link;
+use context_builder::sidebar::{SidebarOptions, SidebarProviders};
+use controllers::{posts, tags};
+use db::DbConn;
+use routes::Urlify;
+
+/// Number of posts listed by `recent_posts` and `popular_posts` if no `count` option is given.
+const DEFAULT_COUNT: i64 = 5;
+
+/// Number of size steps of the tag cloud. Tags get the classes `tag-size-1` to `tag-size-5`.
+const TAG_CLOUD_SIZES: i64 = 5;
+
+thread_local! {
+    static PROVIDERS: SidebarProviders<DbConn> = providers();
+}
+
+/// Loads the dynamic sidebar items of a frontend page with the connection of the route, so a
+/// request never holds more than one.
+pub fn load<T: Serialize>(db: &DbConn, context_builder: &mut ContextBuilder<T>) {
+    PROVIDERS.with(|providers| context_builder.load_sidebar(providers, db));
+}
+
+/// Returns all sidebar providers of this crate.
+fn providers() -> SidebarProviders<DbConn> {
+    SidebarProviders::new()
+        .register("archive_months", archive_months)
+        .register("recent_posts", recent_posts)
+        .register("popular_posts", popular_posts)
+        .register("tag_cloud", tag_cloud)
+}
+
+fn count_option(options: &SidebarOptions) -> i64 {
+    options.get("count")
+        .and_then(|count| count.as_integer())
+        .unwrap_or(DEFAULT_COUNT)
+}
+
+/// Links to every month with published posts.
+fn archive_months(db: &DbConn, _options: &SidebarOptions) -> Option<SidebarItem> {
+    let months = posts::archive_months(db)
+        .map_err(|err| {
+            // TODO add real logging here
+            println!("Error loading archive months: {:?}", err);
+        })
+        .ok()?;
+
+    Some(SidebarItem::Links(
+        months.iter().map(|month| Link::new(month.name(), month.url())).collect(),
+    ))
+}
+
+/// Links to the most recent posts.
+/// Options: `count`
+fn recent_posts(db: &DbConn, options: &SidebarOptions) -> Option<SidebarItem> {
+    let posts = posts::recent(db, count_option(options)).ok()?;
+
+    Some(SidebarItem::Links(
+        posts.iter().map(|post| Link::new(&post.title, post.url())).collect(),
+    ))
+}
+
+/// Links to the most viewed posts.
+/// Options: `count`
+fn popular_posts(db: &DbConn, options: &SidebarOptions) -> Option<SidebarItem> {
+    let posts = posts::popular(db, count_option(options)).ok()?;
+
+    Some(SidebarItem::Links(
+        posts.iter().map(|post| Link::new(&post.title, post.url())).collect(),
+    ))
+}
+
+/// Links to all tags of published posts, with a `tag-size-<n>` class depending on how often
+/// the tag is used.
+/// Options: `limit` to only show the most used tags.
+fn tag_cloud(db: &DbConn, options: &SidebarOptions) -> Option<SidebarItem> {
+    let mut tags = tags::with_counts(db).ok()?;
+
+    if let Some(limit) = options.get("limit").and_then(|limit| limit.as_integer()) {
+        tags.sort_by(|a, b| b.1.cmp(&a.1));
+        tags.truncate(limit as usize);
+        tags.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+    }
+
+    let max = tags.iter().map(|&(_, count)| count).max().unwrap_or(1);
+
+    Some(SidebarItem::Links(
+        tags.iter()
+            .map(|&(ref tag, count)| {
+                let mut link = Link::new(&tag.name, tag.url());
+                let size = (count * TAG_CLOUD_SIZES + max - 1) / max;
+                link.add_class(format!("tag-size-{}", size));
+                link
+            })
+            .collect(),
+    ))
+}
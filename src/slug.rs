@@ -0,0 +1,137 @@
+use unidecode::unidecode;
+
+/// Maximum length of a generated slug (without the `-2`, `-3`, ... suffix).
+const MAX_LENGTH: usize = 80;
+
+/// Creates a url-safe slug from the given text.
+///
+/// Unicode is transliterated to ASCII, everything is lowercased and every run of characters
+/// that are not letters or digits is replaced by a single dash ("-"). Apostrophes are removed
+/// so "Don't panic" becomes "dont-panic". The result never starts or ends with a dash, but
+/// may be empty if the text contains nothing slug-worthy.
+pub fn slugify(text: &str) -> String {
+    let ascii = unidecode(text).to_lowercase();
+    let mut slug = String::with_capacity(ascii.len());
+
+    for c in ascii.chars() {
+        if c.is_ascii() && c.is_alphanumeric() {
+            slug.push(c);
+        } else if c == '\'' {
+            continue;
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    truncate(slug.trim_right_matches('-'))
+}
+
+/// Shortens the slug to at most `MAX_LENGTH` characters, preferably at a dash.
+fn truncate(slug: &str) -> String {
+    if slug.len() <= MAX_LENGTH {
+        return slug.to_string();
+    }
+
+    // the slug is ASCII only, so slicing at any index is fine.
+    let cut = &slug[..MAX_LENGTH];
+    match cut.rfind('-') {
+        Some(i) if i > 0 => cut[..i].to_string(),
+        _ => cut.to_string(),
+    }
+}
+
+/// Returns `slug` if it is not taken yet, otherwise appends `-2`, `-3`, ... until `exists`
+/// reports a free one.
+pub fn unique<F, E>(slug: &str, mut exists: F) -> Result<String, E>
+where
+    F: FnMut(&str) -> Result<bool, E>,
+{
+    if !exists(slug)? {
+        return Ok(slug.to_string());
+    }
+
+    let mut n = 2;
+    loop {
+        let candidate = format!("{}-{}", slug, n);
+        if !exists(&candidate)? {
+            return Ok(candidate);
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn simple_titles() {
+        assert_eq!(slugify("Hello World"), "hello-world");
+        assert_eq!(slugify("hello-world"), "hello-world");
+        assert_eq!(slugify("Rust 1.21 released"), "rust-1-21-released");
+    }
+
+    #[test]
+    fn strips_unsafe_characters() {
+        assert_eq!(slugify("What? 100% sure!"), "what-100-sure");
+        assert_eq!(slugify("C# / F# & .NET"), "c-f-net");
+        assert_eq!(slugify("a/b\\c?d#e%f&g=h"), "a-b-c-d-e-f-g-h");
+        assert_eq!(slugify("<script>alert(1)</script>"), "script-alert-1-script");
+    }
+
+    #[test]
+    fn collapses_dashes_and_whitespace() {
+        assert_eq!(slugify("  lots   of \t space\n"), "lots-of-space");
+        assert_eq!(slugify("--already---dashed--"), "already-dashed");
+        assert_eq!(slugify("a - b -- c"), "a-b-c");
+    }
+
+    #[test]
+    fn removes_apostrophes() {
+        assert_eq!(slugify("Don't panic"), "dont-panic");
+        assert_eq!(slugify("It’s here"), "its-here");
+    }
+
+    #[test]
+    fn transliterates_unicode() {
+        assert_eq!(slugify("Größenwahn über alles"), "grossenwahn-uber-alles");
+        assert_eq!(slugify("Crème brûlée"), "creme-brulee");
+        assert_eq!(slugify("Привет мир"), "privet-mir");
+        assert_eq!(slugify("Ελληνικά"), "ellenika");
+    }
+
+    #[test]
+    fn empty_results() {
+        assert_eq!(slugify(""), "");
+        assert_eq!(slugify("???"), "");
+        assert_eq!(slugify("🎉"), "");
+    }
+
+    #[test]
+    fn truncates_long_titles() {
+        let title = "word ".repeat(50);
+        let slug = slugify(&title);
+        assert!(slug.len() <= MAX_LENGTH);
+        assert!(!slug.ends_with('-'));
+        assert!(slug.starts_with("word-word"));
+
+        let slug = slugify(&"a".repeat(200));
+        assert_eq!(slug.len(), MAX_LENGTH);
+    }
+
+    #[test]
+    fn unique_slugs() {
+        let taken: HashSet<&str> = hashset!("post", "post-2", "other");
+        let exists = |s: &str| -> Result<bool, ()> { Ok(taken.contains(s)) };
+
+        assert_eq!(unique("new", &exists), Ok("new".to_string()));
+        assert_eq!(unique("other", &exists), Ok("other-2".to_string()));
+        assert_eq!(unique("post", &exists), Ok("post-3".to_string()));
+    }
+
+    #[test]
+    fn unique_propagates_errors() {
+        assert_eq!(unique("post", |_| Err("db error")), Err("db error"));
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+
+use super::{SpamCheck, Submission, HONEYPOT_FIELD, TOKEN_FIELD};
+use super::token::TimestampSigner;
+
+/// Rejects submissions that filled in the hidden honeypot field, which only bots do.
+pub struct Honeypot;
+
+impl SpamCheck for Honeypot {
+    fn check(&self, submission: &Submission) -> Result<(), &'static str> {
+        match submission.field(HONEYPOT_FIELD) {
+            Some(value) if !value.is_empty() => Err("Your submission looks like spam."),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Rejects forms that were submitted too fast or too long after they were rendered, using the
+/// signed timestamp in the token field. Logged in users can save as fast or as late as they like.
+pub struct SubmitTime {
+    signer: TimestampSigner,
+    min_seconds: i64,
+    max_seconds: i64,
+}
+
+impl SubmitTime {
+    pub fn new(signer: TimestampSigner, min_seconds: i64, max_seconds: i64) -> SubmitTime {
+        SubmitTime { signer, min_seconds, max_seconds }
+    }
+}
+
+impl SpamCheck for SubmitTime {
+    fn check(&self, submission: &Submission) -> Result<(), &'static str> {
+        if submission.authenticated {
+            return Ok(());
+        }
+
+        let rendered_at = submission.field(TOKEN_FIELD)
+            .and_then(|token| self.signer.verify(token))
+            .ok_or("The form is invalid. Please reload the page and try again.")?;
+        let elapsed = Utc::now().timestamp() - rendered_at;
+
+        if elapsed < self.min_seconds {
+            Err("You submitted the form too quickly. Please try again.")
+        } else if elapsed > self.max_seconds {
+            Err("The form has expired. Please reload the page and try again.")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Rejects submissions of anonymous users with more than `max` links.
+/// Logged in users are trusted to post as many links as they like.
+pub struct LinkCount {
+    max: usize,
+}
+
+impl LinkCount {
+    pub fn new(max: usize) -> LinkCount {
+        LinkCount { max }
+    }
+}
+
+impl SpamCheck for LinkCount {
+    fn check(&self, submission: &Submission) -> Result<(), &'static str> {
+        if submission.authenticated || count_links(submission.values()) <= self.max {
+            Ok(())
+        } else {
+            Err("Your submission contains too many links.")
+        }
+    }
+}
+
+/// Counts everything that looks like a url in the given texts.
+fn count_links<'a, I: Iterator<Item=&'a String>>(texts: I) -> usize {
+    texts
+        .map(|text| text.matches("://").count() + text.matches("www.").count() - text.matches("://www.").count())
+        .sum()
+}
+
+/// Allows at most `limit` submissions per IP address in the given time window.
+/// Submissions of logged in users and without a known address are not limited.
+pub struct RateLimit {
+    limit: usize,
+    window: Duration,
+    submissions: Mutex<HashMap<IpAddr, Vec<Instant>>>,
+}
+
+impl RateLimit {
+    pub fn new(limit: usize, window: Duration) -> RateLimit {
+        RateLimit {
+            limit,
+            window,
+            submissions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl SpamCheck for RateLimit {
+    fn check(&self, submission: &Submission) -> Result<(), &'static str> {
+        let ip = match submission.ip {
+            Some(ip) if !submission.authenticated => ip,
+            _ => return Ok(()),
+        };
+
+        let now = Instant::now();
+        let window = self.window;
+        let mut submissions = self.submissions.lock().expect("rate limit lock");
+
+        for times in submissions.values_mut() {
+            times.retain(|time| now.duration_since(*time) < window);
+        }
+        submissions.retain(|_, times| !times.is_empty());
+
+        let times = submissions.entry(ip).or_insert_with(Vec::new);
+        if times.len() >= self.limit {
+            Err("You submitted too many forms. Please wait a few minutes and try again.")
+        } else {
+            times.push(now);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    #[test]
+    fn count_links_in_texts() {
+        assert_eq!(count_links(texts(&["no links here"]).iter()), 0);
+        assert_eq!(count_links(texts(&["see https://example.com"]).iter()), 1);
+        assert_eq!(count_links(texts(&["https://www.example.com and www.example.org"]).iter()), 2);
+        assert_eq!(count_links(texts(&["[a](http://a.tld)", "<a href=\"ftp://b.tld\">b</a>"]).iter()), 2);
+    }
+
+    fn submission(authenticated: bool) -> Submission {
+        Submission {
+            fields: HashMap::new(),
+            ip: Some("127.0.0.1".parse().unwrap()),
+            authenticated,
+        }
+    }
+
+    #[test]
+    fn logged_in_users_are_not_limited() {
+        let signer = TimestampSigner::new(Some("secret"));
+        assert!(SubmitTime::new(signer, 3, 60).check(&submission(true)).is_ok());
+
+        let limit = RateLimit::new(1, Duration::from_secs(60));
+        assert!(limit.check(&submission(true)).is_ok());
+        assert!(limit.check(&submission(true)).is_ok());
+        assert!(limit.check(&submission(false)).is_ok());
+        assert!(limit.check(&submission(false)).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+pub mod checks;
+pub mod token;
+
+use std::collections::HashMap;
+use std::collections::hash_map::Values;
+use std::io::Read;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use chrono::Utc;
+use rocket::{Data, Outcome, Request, State};
+use rocket::data::{self, FromData};
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, FromForm, FormItems};
+use serde::Serialize;
+
+use config::SpamConfig;
+use context_builder::ContextBuilder;
+use context_builder::csrf;
+use db::models::User;
+use self::checks::{Honeypot, SubmitTime, LinkCount, RateLimit};
+use self::token::TimestampSigner;
+
+/// Name of the field that has to stay empty. It is hidden with css, so only bots fill it in.
+pub const HONEYPOT_FIELD: &'static str = "website";
+
+/// Name of the hidden field containing the signed time the form was rendered at.
+pub const TOKEN_FIELD: &'static str = "form_token";
+
+/// Maximum size of a checked form submission in bytes.
+const LIMIT: u64 = 1024 * 1024;
+
+/// A form submission as seen by the spam checks.
+#[derive(Debug)]
+pub struct Submission {
+    fields: HashMap<String, String>,
+    /// The address the submission was sent from, if known.
+    pub ip: Option<IpAddr>,
+    /// Whether the submission was sent by a logged in user.
+    pub authenticated: bool,
+}
+
+impl Submission {
+    /// Returns the decoded value of the given field.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(|value| value.as_str())
+    }
+
+    /// Returns the decoded values of all fields.
+    pub fn values(&self) -> Values<String, String> {
+        self.fields.values()
+    }
+}
+
+/// A single heuristic used to detect spam.
+/// Returns a message that is shown to the user if the submission is rejected.
+pub trait SpamCheck: Send + Sync {
+    fn check(&self, submission: &Submission) -> Result<(), &'static str>;
+}
+
+/// Runs form submissions through a list of [`SpamCheck`]s. Needs to be managed by rocket for
+/// [`SpamChecked`] and [`SpamToken`] to work.
+///
+/// [`SpamCheck`]: trait.SpamCheck.html
+/// [`SpamChecked`]: struct.SpamChecked.html
+/// [`SpamToken`]: struct.SpamToken.html
+pub struct SpamFilter {
+    signer: TimestampSigner,
+    checks: Vec<Box<SpamCheck>>,
+}
+
+impl SpamFilter {
+    /// Creates a filter without any checks. `signer` is used to create the form tokens.
+    pub fn new(signer: TimestampSigner) -> SpamFilter {
+        SpamFilter {
+            signer,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Adds a check. Checks run in the order they were added, the first one to reject a
+    /// submission wins.
+    pub fn add<C: SpamCheck + 'static>(mut self, check: C) -> SpamFilter {
+        self.checks.push(Box::new(check));
+        self
+    }
+
+    pub fn check(&self, submission: &Submission) -> Result<(), &'static str> {
+        for check in &self.checks {
+            check.check(submission)?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new token for the [`TOKEN_FIELD`].
+    ///
+    /// [`TOKEN_FIELD`]: constant.TOKEN_FIELD.html
+    pub fn token(&self) -> String {
+        self.signer.sign(Utc::now().timestamp())
+    }
+}
+
+/// Creates the filter with all checks, configured by the `[spam]` section of `Satellite.toml`.
+pub fn filter(config: &SpamConfig) -> SpamFilter {
+    let signer = TimestampSigner::new(config.secret.as_ref().map(|secret| secret.as_str()));
+
+    SpamFilter::new(signer.clone())
+        .add(Honeypot)
+        .add(SubmitTime::new(signer, config.min_submit_seconds, config.max_submit_seconds))
+        .add(LinkCount::new(config.max_links))
+        .add(RateLimit::new(config.rate_limit, Duration::from_secs(config.rate_limit_seconds)))
+}
+
+/// Data guard that parses a form like [`CsrfForm`] and runs it through the [`SpamFilter`].
+/// Rejected submissions are still parsed, so the form can be rendered again with the error.
+///
+/// ```
+/// #[post("/post/<id>/comments", data = "<comment>")]
+/// fn new_comment(id: i32, comment: SpamChecked<NewComment>) -> ... {
+///     let (comment, verdict) = comment.into_parts();
+///     // ...
+/// }
+/// ```
+///
+/// [`CsrfForm`]: ../../context_builder/csrf/struct.CsrfForm.html
+/// [`SpamFilter`]: struct.SpamFilter.html
+pub struct SpamChecked<T> {
+    form: T,
+    verdict: Result<(), &'static str>,
+}
+
+impl<T> SpamChecked<T> {
+    /// Returns the form and the result of the spam check. The error uses the usual errors map,
+    /// with the message as `general` error.
+    pub fn into_parts(self) -> (T, Result<(), HashMap<String, String>>) {
+        let verdict = self.verdict.map_err(|message| convert_args!(hashmap!(
+            "general" => message,
+        )));
+
+        (self.form, verdict)
+    }
+}
+
+impl<T> FromData for SpamChecked<T>
+where
+    T: for<'f> FromForm<'f>,
+{
+    type Error = Option<String>;
+
+    fn from_data(request: &Request, data: Data) -> data::Outcome<Self, Self::Error> {
+        if !request.content_type().map_or(false, |ct| ct.is_form()) {
+            return Outcome::Forward(data);
+        }
+
+        let filter = match request.guard::<State<SpamFilter>>() {
+            Outcome::Success(filter) => filter,
+            _ => return Outcome::Failure((Status::InternalServerError, None)),
+        };
+
+        let mut body = String::new();
+        if let Err(e) = data.open().take(LIMIT).read_to_string(&mut body) {
+            return Outcome::Failure((Status::InternalServerError, Some(format!("{:?}", e))));
+        }
+
+        if !csrf::verify_form(request, &body) {
+            return Outcome::Failure((Status::BadRequest, Some("invalid csrf token".to_string())));
+        }
+
+        let submission = Submission {
+            fields: FormItems::from(body.as_str())
+                .filter_map(|(key, value)| {
+                    let value = value.url_decode().ok()?;
+                    Some((key.as_str().to_string(), value))
+                })
+                .collect(),
+            ip: request.remote().map(|addr| addr.ip()),
+            authenticated: request.guard::<User>().succeeded().is_some(),
+        };
+        let verdict = filter.check(&submission);
+
+        let form = {
+            let mut items = FormItems::from(body.as_str());
+            match T::from_form(&mut items, false) {
+                Ok(form) => form,
+                Err(_) => return Outcome::Failure((Status::UnprocessableEntity, None)),
+            }
+        };
+
+        Outcome::Success(SpamChecked { form, verdict })
+    }
+}
+
+/// Request guard creating a fresh token for forms checked with [`SpamChecked`].
+///
+/// [`SpamChecked`]: struct.SpamChecked.html
+pub struct SpamToken(String);
+
+impl SpamToken {
+    /// Adds the token as hidden field to the context.
+    pub fn add_to<'s, T: Serialize>(self, context_builder: &mut ContextBuilder<'s, T>) {
+        context_builder.add_hidden_field(TOKEN_FIELD, self.0);
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for SpamToken {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<SpamToken, ()> {
+        let filter = request.guard::<State<SpamFilter>>()?;
+        Outcome::Success(SpamToken(filter.token()))
+    }
+}
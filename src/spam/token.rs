@@ -0,0 +1,98 @@
+use std::str;
+
+use ring::{digest, hmac};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// Creates and verifies signed unix timestamps.
+/// Used to tell how long ago a form was rendered, without having to store anything.
+#[derive(Clone)]
+pub struct TimestampSigner {
+    key: Vec<u8>,
+}
+
+impl TimestampSigner {
+    /// Creates a signer using the given secret or a random one.
+    pub fn new(secret: Option<&str>) -> TimestampSigner {
+        let key = match secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => {
+                let mut key = vec![0; 32];
+                SystemRandom::new().fill(&mut key).expect("generate random spam token key");
+                key
+            },
+        };
+
+        TimestampSigner { key }
+    }
+
+    fn signing_key(&self) -> hmac::SigningKey {
+        hmac::SigningKey::new(&digest::SHA256, &self.key)
+    }
+
+    /// Returns a token in the form `<timestamp>-<hex encoded signature>`.
+    pub fn sign(&self, timestamp: i64) -> String {
+        let signature = hmac::sign(&self.signing_key(), timestamp.to_string().as_bytes());
+        format!("{}-{}", timestamp, to_hex(signature.as_ref()))
+    }
+
+    /// Returns the timestamp of a token created by [`sign`] if the signature is valid.
+    ///
+    /// [`sign`]: #method.sign
+    pub fn verify(&self, token: &str) -> Option<i64> {
+        let mut parts = token.splitn(2, '-');
+        let timestamp = parts.next()?;
+        let signature = from_hex(parts.next()?)?;
+
+        hmac::verify_with_own_key(&self.signing_key(), timestamp.as_bytes(), &signature).ok()?;
+        timestamp.parse().ok()
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| str::from_utf8(pair).ok().and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_signed_timestamp() {
+        let signer = TimestampSigner::new(Some("secret"));
+        let token = signer.sign(1510000000);
+
+        assert!(token.starts_with("1510000000-"));
+        assert_eq!(signer.verify(&token), Some(1510000000));
+    }
+
+    #[test]
+    fn reject_tampered_tokens() {
+        let signer = TimestampSigner::new(Some("secret"));
+        let token = signer.sign(1510000000);
+        let signature = token.splitn(2, '-').nth(1).unwrap();
+
+        assert_eq!(signer.verify(&format!("1500000000-{}", signature)), None);
+        assert_eq!(signer.verify("1510000000"), None);
+        assert_eq!(signer.verify("1510000000-xyz"), None);
+        assert_eq!(signer.verify(""), None);
+    }
+
+    #[test]
+    fn reject_tokens_of_other_keys() {
+        let token = TimestampSigner::new(Some("secret")).sign(1510000000);
+
+        assert_eq!(TimestampSigner::new(Some("other")).verify(&token), None);
+        assert_eq!(TimestampSigner::new(None).verify(&token), None);
+    }
+}
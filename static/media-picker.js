@@ -0,0 +1,33 @@
+// Inserts images from the media library into the textarea named by `data-target`.
+document.querySelectorAll('.media-picker').forEach(function (picker) {
+    var target = document.getElementById(picker.dataset.target);
+    var items = picker.querySelector('.media-picker-list');
+
+    picker.querySelector('.media-picker-toggle').addEventListener('click', function () {
+        if (!items.hidden) {
+            items.hidden = true;
+            return;
+        }
+
+        fetch(picker.dataset.url, { credentials: 'same-origin' })
+            .then(function (response) { return response.text(); })
+            .then(function (html) {
+                items.innerHTML = html;
+                items.hidden = false;
+            });
+    });
+
+    items.addEventListener('click', function (event) {
+        var item = event.target.closest('.media-picker-item');
+        if (!item) {
+            return;
+        }
+
+        var start = target.selectionStart;
+        var end = target.selectionEnd;
+        target.value = target.value.slice(0, start) + item.dataset.markdown + target.value.slice(end);
+        target.selectionStart = target.selectionEnd = start + item.dataset.markdown.length;
+        target.focus();
+        items.hidden = true;
+    });
+});